@@ -23,11 +23,18 @@ if (arg === "--help" || arg === "-h") {
 	console.log("       mdcat                 Open mdcat app")
 	console.log("       mdcat README.md       Open file in mdcat")
 	console.log("       mdcat .               Open directory (finds README.md or first .md)")
+	console.log("       mdcat check-links [root] [--json]  Report broken links and anchors; exits 1 if any")
 	console.log("       mdcat install         Install mdcat.app to /Applications (or ~/Applications fallback)")
 	console.log("       mdcat install --user  Install to ~/Applications")
 	process.exit(0)
 }
 
+if (arg === "check-links") {
+	// Runs headless in the app binary; its exit code is the check result.
+	const result = await $`${BIN_PATH} ${process.argv.slice(2)}`.nothrow()
+	process.exit(result.exitCode)
+}
+
 if (arg === "install") {
 	const args = process.argv.slice(3)
 
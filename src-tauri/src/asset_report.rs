@@ -0,0 +1,475 @@
+//! Asset inventory for a root: every image / media file under it (see
+//! `file_tree::ASSET_EXTENSIONS`) is matched against the references in its
+//! markdown files (links, images, `![[embeds]]` and HTML `src` / `href`
+//! attributes) to list assets nothing references and references to assets
+//! that don't exist. References from the site around the pages also keep an
+//! asset in use: HTML templates, stylesheets' `url(...)`, paths in
+//! `mkdocs.yml` / `book.toml` and anything in mdBook's theme folder.
+//!
+//! Unused assets can be moved to the app's trash folder, one subfolder per
+//! trashing with a manifest of the root they came from, so they can be
+//! listed and put back at their path under the root.
+
+use crate::book_nav;
+use crate::config::TreeConfig;
+use crate::file_meta::FenceState;
+use crate::file_tree;
+use crate::link_graph::{self, Link};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+/// Folder under the app data dir that trashed assets are moved into, one
+/// subfolder per trashing.
+const TRASH_DIRNAME: &str = "trash";
+
+/// Written into each trashing's subfolder, next to the moved assets.
+const MANIFEST_FILENAME: &str = "mdcat-trash.json";
+
+/// Text files whose references keep assets in use besides markdown.
+const SITE_EXTENSIONS: &[&str] = &["html", "htm", "css"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UnusedAsset {
+    pub path: String,
+    pub rel_path: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MissingAsset {
+    /// File containing the reference.
+    pub path: String,
+    pub rel_path: String,
+    /// 1-based line and char column of the reference start.
+    pub line: usize,
+    pub column: usize,
+    /// Destination as written.
+    pub target: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AssetReport {
+    pub root: String,
+    pub assets_checked: usize,
+    pub references_checked: usize,
+    pub unused: Vec<UnusedAsset>,
+    pub missing: Vec<MissingAsset>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrashOutcome {
+    /// Folder the assets were moved into; `None` if nothing was moved.
+    pub trash_dir: Option<String>,
+    pub moved: Vec<String>,
+    /// Referenced again (or gone) since the report; left in place.
+    pub refused: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TrashManifest {
+    root: String,
+    trashed_at: u64,
+}
+
+/// One trashing, as listed by `list_trash`.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrashEntry {
+    /// Subfolder name; what `restore_trashed` takes.
+    pub id: String,
+    pub root: String,
+    /// Seconds since the epoch.
+    pub trashed_at: u64,
+    /// Paths relative to `root` still in the trash.
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreOutcome {
+    /// Paths put back, relative to the root.
+    pub restored: Vec<String>,
+    /// Something exists at the original path (or nothing in the trash); left in the trash.
+    pub refused: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+fn has_asset_ext(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| {
+        let ext = ext.to_string_lossy();
+        file_tree::ASSET_EXTENSIONS
+            .iter()
+            .any(|a| a.eq_ignore_ascii_case(&ext))
+    })
+}
+
+fn rel(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .to_string()
+}
+
+/// `src="..."` / `href="..."` values outside code fences, with their 1-based
+/// line and char column.
+fn html_references(content: &str) -> Vec<(usize, usize, &str)> {
+    let mut refs = Vec::new();
+    let mut fences = FenceState::default();
+    for (i, line) in content.lines().enumerate() {
+        if fences.is_code(line) {
+            continue;
+        }
+        for attr in ["src=", "href="] {
+            for (start, _) in line.match_indices(attr) {
+                if !line[..start].ends_with(char::is_whitespace) {
+                    continue;
+                }
+                let rest = &line[start + attr.len()..];
+                let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+                    continue;
+                };
+                let value = &rest[1..];
+                let Some(end) = value.find(quote) else {
+                    continue;
+                };
+                let column = line[..start].chars().count() + 1;
+                refs.push((i + 1, column, &value[..end]));
+            }
+        }
+    }
+    refs
+}
+
+/// `url(...)` values of a stylesheet line with their char column.
+fn css_urls(line: &str) -> Vec<(usize, &str)> {
+    line.match_indices("url(")
+        .filter_map(|(start, _)| {
+            let rest = &line[start + 4..];
+            let value = rest[..rest.find(')')?].trim();
+            let value = value.trim_matches(|c| c == '"' || c == '\'');
+            Some((line[..start].chars().count() + 1, value))
+        })
+        .collect()
+}
+
+/// Words of a config line that name an asset file, e.g. `logo: img/logo.png`
+/// or `additional-css = ["theme/x.css"]`.
+fn config_paths(line: &str) -> Vec<&str> {
+    line.split(|c: char| c.is_whitespace() || "\"',[]=".contains(c))
+        .map(|word| word.trim_end_matches(':'))
+        .filter(|word| has_asset_ext(Path::new(word)))
+        .collect()
+}
+
+/// Assets the site around the pages uses: paths in the book config and in
+/// HTML / CSS files, plus everything in mdBook's theme folder.
+fn site_references(root: &Path, config: &TreeConfig) -> Vec<PathBuf> {
+    let mut used = Vec::new();
+    let content_dir = book_nav::content_dir(root);
+    if let Some(file) = book_nav::config_file(root) {
+        let content = std::fs::read_to_string(&file).unwrap_or_default();
+        for word in content.lines().flat_map(config_paths) {
+            let word = word.trim_start_matches('/');
+            // MkDocs takes paths like `logo` from docs_dir, mdBook from the root
+            used.extend(
+                std::iter::once(root)
+                    .chain(content_dir.as_deref())
+                    .map(|dir| file_tree::normalize(&dir.join(word))),
+            );
+        }
+    }
+    if let Some(theme) = book_nav::mdbook_theme_dir(root) {
+        used.extend(
+            file_tree::scan_asset_files(root, config)
+                .into_iter()
+                .filter(|p| p.starts_with(&theme)),
+        );
+    }
+    for file in file_tree::scan_files_by_ext(root, config, SITE_EXTENSIONS) {
+        let Ok(content) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        let targets: Vec<&str> = if file.extension().is_some_and(|e| e == "css") {
+            content
+                .lines()
+                .flat_map(|line| css_urls(line).into_iter().map(|(_, url)| url))
+                .collect()
+        } else {
+            html_references(&content)
+                .into_iter()
+                .map(|(_, _, target)| target)
+                .collect()
+        };
+        for target in targets {
+            used.extend(link_graph::resolve(target, &file, root).0);
+        }
+    }
+    used
+}
+
+/// Cross-reference the assets under `root` with every markdown file's links.
+pub fn report(root: &Path, config: &TreeConfig) -> AssetReport {
+    let mut assets: Vec<(PathBuf, String)> = file_tree::scan_asset_files(root, config)
+        .into_iter()
+        .map(|path| {
+            let rel = rel(&path, root).replace('\\', "/");
+            (path, rel)
+        })
+        .collect();
+    assets.sort();
+
+    let mut files: Vec<(PathBuf, Vec<Link>)> = link_graph::with_links(root, config, |files| {
+        files
+            .iter()
+            .map(|(path, links)| (path.clone(), links.clone()))
+            .collect()
+    });
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // (source, line, column, target as written, resolved path)
+    let mut refs: Vec<(&Path, usize, usize, String, PathBuf)> = Vec::new();
+    let mut used: HashSet<PathBuf> = HashSet::new();
+    for (source, links) in &files {
+        for link in links {
+            // an ambiguous `![[name.png]]` uses every asset it could mean
+            used.extend(link.candidates.iter().map(PathBuf::from));
+            let path = match &link.path {
+                Some(path) => PathBuf::from(path),
+                None if link.kind.is_wiki() && link.candidates.is_empty() => {
+                    let page = link.target.split('#').next().unwrap_or_default().trim();
+                    if !has_asset_ext(Path::new(page)) {
+                        continue;
+                    }
+                    root.join(page.trim_start_matches('/'))
+                }
+                None => continue,
+            };
+            refs.push((source, link.line, link.column, link.target.clone(), path));
+        }
+        let Ok(content) = std::fs::read_to_string(source) else {
+            continue;
+        };
+        for (line, column, target) in html_references(&content) {
+            if let (Some(path), _) = link_graph::resolve(target, source, root) {
+                refs.push((source, line, column, target.to_string(), path));
+            }
+        }
+    }
+
+    let site = site_references(root, config);
+    let references_checked = refs.len() + site.len();
+    used.extend(site);
+
+    let mut missing = Vec::new();
+    for (source, line, column, target, path) in &refs {
+        if path.exists() {
+            used.insert(path.clone());
+        } else if has_asset_ext(path) {
+            missing.push(MissingAsset {
+                path: source.to_string_lossy().to_string(),
+                rel_path: rel(source, root),
+                line: *line,
+                column: *column,
+                target: target.clone(),
+            });
+        }
+    }
+
+    let unused = assets
+        .iter()
+        .filter(|(path, _)| !used.contains(path))
+        .map(|(path, rel_path)| UnusedAsset {
+            path: path.to_string_lossy().to_string(),
+            rel_path: rel_path.clone(),
+            size: std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        })
+        .collect();
+    AssetReport {
+        root: root.to_string_lossy().to_string(),
+        assets_checked: assets.len(),
+        references_checked,
+        unused,
+        missing,
+    }
+}
+
+/// Move `path` to `dest`, copying across filesystems.
+fn move_file(path: &Path, dest: &Path) -> std::io::Result<()> {
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if std::fs::rename(path, dest).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(path, dest)?;
+    std::fs::remove_file(path)
+}
+
+fn trash_root(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("No app data dir: {}", e))?
+        .join(TRASH_DIRNAME))
+}
+
+/// Files under `dir` (recursively) as `/`-separated paths relative to `base`.
+fn files_under(dir: &Path, base: &Path, out: &mut Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            files_under(&path, base, out);
+        } else if path.file_name().is_some_and(|n| n != MANIFEST_FILENAME) {
+            out.push(rel(&path, base).replace('\\', "/"));
+        }
+    }
+}
+
+/// Move the assets in `paths` that are still unused under `root` into a new
+/// folder of the app's trash, at their path relative to the root.
+pub fn trash_unused(
+    app: &tauri::AppHandle,
+    root: &Path,
+    config: &TreeConfig,
+    paths: &[String],
+) -> Result<TrashOutcome, String> {
+    let unused: HashSet<String> = report(root, config)
+        .unused
+        .into_iter()
+        .map(|a| a.path)
+        .collect();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let trash = trash_root(app)?;
+    let mut trash_dir = trash.join(now.as_millis().to_string());
+    let mut n = 1;
+    while trash_dir.exists() {
+        trash_dir = trash.join(format!("{}-{}", now.as_millis(), n));
+        n += 1;
+    }
+
+    let mut outcome = TrashOutcome {
+        trash_dir: None,
+        moved: Vec::new(),
+        refused: Vec::new(),
+        errors: Vec::new(),
+    };
+    for path in paths {
+        if !unused.contains(path) {
+            outcome.refused.push(path.clone());
+            continue;
+        }
+        if outcome.moved.is_empty() && !trash_dir.exists() {
+            // the manifest goes first so a partial trashing can still be restored
+            let manifest = TrashManifest {
+                root: root.to_string_lossy().to_string(),
+                trashed_at: now.as_secs(),
+            };
+            let json = serde_json::to_string(&manifest).map_err(|e| e.to_string())?;
+            std::fs::create_dir_all(&trash_dir)
+                .and_then(|()| std::fs::write(trash_dir.join(MANIFEST_FILENAME), json))
+                .map_err(|e| format!("Failed to create {}: {}", trash_dir.display(), e))?;
+        }
+        let source = Path::new(path);
+        let dest = trash_dir.join(source.strip_prefix(root).unwrap_or(source));
+        match move_file(source, &dest) {
+            Ok(()) => outcome.moved.push(path.clone()),
+            Err(e) => outcome
+                .errors
+                .push(format!("Failed to move {}: {}", path, e)),
+        }
+    }
+    if !outcome.moved.is_empty() {
+        eprintln!(
+            "[mdcat] trashed {} assets into {}",
+            outcome.moved.len(),
+            trash_dir.display()
+        );
+        outcome.trash_dir = Some(trash_dir.to_string_lossy().to_string());
+    }
+    Ok(outcome)
+}
+
+/// Trashings that still hold files, newest first.
+pub fn list_trash(app: &tauri::AppHandle) -> Result<Vec<TrashEntry>, String> {
+    let Ok(dirs) = std::fs::read_dir(trash_root(app)?) else {
+        return Ok(Vec::new());
+    };
+    let mut entries = Vec::new();
+    for dir in dirs.flatten().map(|d| d.path()) {
+        let Some(manifest) = std::fs::read_to_string(dir.join(MANIFEST_FILENAME))
+            .ok()
+            .and_then(|json| serde_json::from_str::<TrashManifest>(&json).ok())
+        else {
+            continue;
+        };
+        let mut files = Vec::new();
+        files_under(&dir, &dir, &mut files);
+        if files.is_empty() {
+            continue;
+        }
+        files.sort();
+        entries.push(TrashEntry {
+            id: dir
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+            root: manifest.root,
+            trashed_at: manifest.trashed_at,
+            files,
+        });
+    }
+    entries.sort_by(|a, b| b.trashed_at.cmp(&a.trashed_at).then(b.id.cmp(&a.id)));
+    Ok(entries)
+}
+
+/// Move `files` (paths relative to the root, as listed) of trashing `id`
+/// back to where they were; the trashing's folder goes once it's empty.
+pub fn restore_trashed(
+    app: &tauri::AppHandle,
+    id: &str,
+    files: &[String],
+) -> Result<RestoreOutcome, String> {
+    let is_name = |p: &Path| p.components().all(|c| matches!(c, Component::Normal(_)));
+    if !is_name(Path::new(id)) || Path::new(id).components().count() != 1 {
+        return Err(format!("Invalid trash id: {}", id));
+    }
+    let dir = trash_root(app)?.join(id);
+    let manifest: TrashManifest = std::fs::read_to_string(dir.join(MANIFEST_FILENAME))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .ok_or_else(|| format!("Nothing in the trash under {}", id))?;
+    let root = PathBuf::from(&manifest.root);
+
+    let mut outcome = RestoreOutcome {
+        restored: Vec::new(),
+        refused: Vec::new(),
+        errors: Vec::new(),
+    };
+    for file in files {
+        let (source, dest) = (dir.join(file), root.join(file));
+        if !is_name(Path::new(file)) || !source.is_file() || dest.exists() {
+            outcome.refused.push(file.clone());
+            continue;
+        }
+        match move_file(&source, &dest) {
+            Ok(()) => outcome.restored.push(file.clone()),
+            Err(e) => outcome
+                .errors
+                .push(format!("Failed to restore {}: {}", file, e)),
+        }
+    }
+    let mut left = Vec::new();
+    files_under(&dir, &dir, &mut left);
+    if left.is_empty() {
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+    Ok(outcome)
+}
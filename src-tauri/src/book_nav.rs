@@ -0,0 +1,294 @@
+//! Declared reading order for documentation sites: mdBook's `SUMMARY.md`
+//! (under `book.toml`'s `src`, or at the root of a book without one) and the
+//! `nav:` key of MkDocs' `mkdocs.yml`. Used by the "book order" view
+//! (`SortKey::Book`), see `file_tree::assemble_book_tree`.
+//!
+//! `mkdocs.yml` is read with a small line parser for the `nav:` subset rather
+//! than a YAML library: real-world files carry `!!python/name` tags that
+//! strict parsers reject.
+
+use crate::file_tree;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BookKind {
+    MdBook,
+    MkDocs,
+}
+
+/// One entry of the declared navigation.
+#[derive(Debug, Clone, Default)]
+pub struct NavItem {
+    /// Declared title; `None` for bare MkDocs entries like `- guide.md`.
+    pub title: Option<String>,
+    /// Absolute path of the page; `None` for sections, parts and drafts.
+    pub path: Option<PathBuf>,
+    pub children: Vec<NavItem>,
+}
+
+const MKDOCS_FILENAMES: &[&str] = &["mkdocs.yml", "mkdocs.yaml"];
+const SUMMARY_FILENAME: &str = "SUMMARY.md";
+
+/// Files whose change alters the navigation of a book rooted at their dir.
+pub fn is_nav_config(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default();
+    name == "book.toml" || MKDOCS_FILENAMES.iter().any(|n| name == *n)
+}
+
+/// Which kind of book `root` is, if any, without parsing the navigation.
+pub fn detect(root: &Path) -> Option<BookKind> {
+    if root.join("book.toml").is_file() {
+        Some(BookKind::MdBook)
+    } else if MKDOCS_FILENAMES.iter().any(|n| root.join(n).is_file()) {
+        Some(BookKind::MkDocs)
+    } else if root.join(SUMMARY_FILENAME).is_file() {
+        Some(BookKind::MdBook)
+    } else {
+        None
+    }
+}
+
+/// Parse the navigation of the book at `root`.
+pub fn load(root: &Path) -> Option<Vec<NavItem>> {
+    match detect(root)? {
+        BookKind::MdBook => {
+            let src = mdbook_src_dir(root);
+            let summary = std::fs::read_to_string(src.join(SUMMARY_FILENAME)).ok()?;
+            Some(parse_summary(&summary, &src))
+        }
+        BookKind::MkDocs => {
+            let config = MKDOCS_FILENAMES
+                .iter()
+                .find_map(|n| std::fs::read_to_string(root.join(n)).ok())?;
+            let docs_dir = top_level_value(&config, "docs_dir").unwrap_or_else(|| "docs".into());
+            Some(parse_mkdocs_nav(&config, &root.join(docs_dir)))
+        }
+    }
+}
+
+/// The book's config file at `root` (`book.toml` or `mkdocs.yml`).
+pub fn config_file(root: &Path) -> Option<PathBuf> {
+    std::iter::once("book.toml")
+        .chain(MKDOCS_FILENAMES.iter().copied())
+        .map(|n| root.join(n))
+        .find(|p| p.is_file())
+}
+
+/// Folder the book's pages live in: mdBook's `src`, MkDocs' `docs_dir`.
+pub fn content_dir(root: &Path) -> Option<PathBuf> {
+    match detect(root)? {
+        BookKind::MdBook => Some(mdbook_src_dir(root)),
+        BookKind::MkDocs => {
+            let config = std::fs::read_to_string(config_file(root)?).ok()?;
+            let docs_dir = top_level_value(&config, "docs_dir").unwrap_or_else(|| "docs".into());
+            Some(root.join(docs_dir))
+        }
+    }
+}
+
+/// mdBook's theme folder (`[output.html] theme`, defaulting to `theme`),
+/// whose files mdBook picks up by name.
+pub fn mdbook_theme_dir(root: &Path) -> Option<PathBuf> {
+    let table = mdbook_config(root)?;
+    let theme = table
+        .get("output")
+        .and_then(|o| o.get("html")?.get("theme")?.as_str())
+        .unwrap_or("theme");
+    Some(root.join(theme))
+}
+
+fn mdbook_config(root: &Path) -> Option<toml::Table> {
+    std::fs::read_to_string(root.join("book.toml"))
+        .ok()?
+        .parse::<toml::Table>()
+        .ok()
+}
+
+/// `[book] src` from `book.toml`, defaulting to `src`; the root itself
+/// when there's no `book.toml` and `SUMMARY.md` sits at the root.
+fn mdbook_src_dir(root: &Path) -> PathBuf {
+    if !root.join("book.toml").is_file() {
+        return root.to_path_buf();
+    }
+    let src = mdbook_config(root)
+        .and_then(|t| t.get("book")?.get("src")?.as_str().map(String::from))
+        .unwrap_or_else(|| "src".into());
+    root.join(src)
+}
+
+/// Resolve a link target from a nav file; external URLs yield `None`.
+fn resolve_link(base: &Path, target: &str) -> Option<PathBuf> {
+    let target = target.trim();
+    let target = target.split('#').next().unwrap_or("");
+    if target.is_empty() || target.contains("://") || target.starts_with("mailto:") {
+        return None;
+    }
+    Some(file_tree::normalize(&base.join(target.replace("%20", " "))))
+}
+
+/// `[title](target)` at the start of `text`.
+fn parse_link(text: &str) -> Option<(String, String)> {
+    let rest = text.strip_prefix('[')?;
+    let close = rest.rfind("](")?;
+    let target = rest[close + 2..].strip_suffix(')')?;
+    Some((rest[..close].to_string(), target.to_string()))
+}
+
+/// mdBook `SUMMARY.md`: optional `# Summary` title, prefix chapters as bare
+/// links, numbered chapters as nested list items, `# Part` headings grouping
+/// the chapters after them, and suffix chapters as bare links at the end.
+fn parse_summary(content: &str, src: &Path) -> Vec<NavItem> {
+    let mut flat: Vec<(usize, NavItem)> = Vec::new();
+    let mut seen_item = false;
+    // list items nest one level deeper once a part heading has been seen
+    let mut part_offset = 0;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if let Some(heading) = trimmed.strip_prefix("# ") {
+            if seen_item {
+                flat.push((
+                    0,
+                    NavItem {
+                        title: Some(heading.trim().to_string()),
+                        ..NavItem::default()
+                    },
+                ));
+                part_offset = 1;
+            }
+            continue;
+        }
+
+        let indent = line.len() - line.trim_start().len();
+        let (depth, link) = match trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+        {
+            Some(item) => (part_offset + indent, item.trim()),
+            None if trimmed.starts_with('[') => (0, trimmed),
+            None => continue,
+        };
+        let Some((title, target)) = parse_link(link) else {
+            continue;
+        };
+        seen_item = true;
+        flat.push((
+            depth,
+            NavItem {
+                title: Some(title),
+                path: resolve_link(src, &target),
+                children: Vec::new(),
+            },
+        ));
+    }
+
+    nest(flat)
+}
+
+/// Value of a top-level `key: value` line in a YAML file.
+fn top_level_value(yaml: &str, key: &str) -> Option<String> {
+    yaml.lines().find_map(|line| {
+        let value = line.strip_prefix(key)?.strip_prefix(':')?;
+        let value = unquote(value.split(" #").next().unwrap_or("").trim());
+        (!value.is_empty()).then(|| value.to_string())
+    })
+}
+
+fn unquote(s: &str) -> &str {
+    for q in ['"', '\''] {
+        if let Some(inner) = s.strip_prefix(q).and_then(|s| s.strip_suffix(q)) {
+            return inner;
+        }
+    }
+    s
+}
+
+/// Split a nav entry into `(title, value)`; bare entries have no title.
+fn split_entry(entry: &str) -> (Option<String>, String) {
+    let entry = entry.trim();
+    for q in ['"', '\''] {
+        if let Some(rest) = entry.strip_prefix(q) {
+            if let Some(end) = rest.find(q) {
+                let value = rest[end + 1..].trim_start().strip_prefix(':').unwrap_or("");
+                return (
+                    Some(rest[..end].to_string()),
+                    unquote(value.trim()).to_string(),
+                );
+            }
+        }
+    }
+    match entry
+        .find(": ")
+        .or_else(|| entry.strip_suffix(':').map(|e| e.len()))
+    {
+        Some(i) => (
+            Some(entry[..i].trim().to_string()),
+            unquote(entry[i + 1..].trim()).to_string(),
+        ),
+        None => (None, unquote(entry).to_string()),
+    }
+}
+
+/// MkDocs `nav:`: `- Title: page.md`, bare `- page.md`, and `- Section:`
+/// followed by deeper entries. Paths are relative to `docs_dir`.
+fn parse_mkdocs_nav(yaml: &str, docs_dir: &Path) -> Vec<NavItem> {
+    let mut flat: Vec<(usize, NavItem)> = Vec::new();
+    let mut in_nav = false;
+
+    for line in yaml.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        if indent == 0 && !trimmed.starts_with('-') {
+            in_nav = trimmed.trim_end() == "nav:";
+            continue;
+        }
+        if !in_nav {
+            continue;
+        }
+        let Some(entry) = trimmed.strip_prefix("- ") else {
+            continue;
+        };
+        let (title, value) = split_entry(entry);
+        flat.push((
+            indent,
+            NavItem {
+                title,
+                path: resolve_link(docs_dir, &value),
+                children: Vec::new(),
+            },
+        ));
+    }
+
+    nest(flat)
+}
+
+/// Turn `(depth, item)` pairs in document order into a tree: each item
+/// becomes a child of the nearest preceding item with a smaller depth.
+fn nest(flat: Vec<(usize, NavItem)>) -> Vec<NavItem> {
+    fn pop(stack: &mut Vec<(usize, NavItem)>, top: &mut Vec<NavItem>) {
+        if let Some((_, item)) = stack.pop() {
+            match stack.last_mut() {
+                Some((_, parent)) => parent.children.push(item),
+                None => top.push(item),
+            }
+        }
+    }
+
+    let mut stack: Vec<(usize, NavItem)> = Vec::new();
+    let mut top = Vec::new();
+    for (depth, item) in flat {
+        while stack.last().is_some_and(|(d, _)| *d >= depth) {
+            pop(&mut stack, &mut top);
+        }
+        stack.push((depth, item));
+    }
+    while !stack.is_empty() {
+        pop(&mut stack, &mut top);
+    }
+    top
+}
@@ -1,38 +1,216 @@
-use crate::file_tree::{self, FileNode};
+use crate::asset_report::{self, AssetReport, RestoreOutcome, TrashEntry, TrashOutcome};
+use crate::book_nav::{self, BookKind};
+use crate::config::{self, LoadedConfig, SortKey, SortOrder, TreeConfig};
+use crate::file_tree::{self, FileNode, ScanScope};
 use crate::file_watcher;
+use crate::git_repo::{self, GitRepo};
+use crate::git_status;
+use crate::heading_index::{self, Heading, HeadingMatch};
 use crate::instance_registry;
-use std::path::PathBuf;
+use crate::link_check::{self, LinkReport};
+use crate::link_complete::{self, LinkCompletion};
+use crate::link_graph::{self, Backlink, Link, WikiResolution};
+use crate::link_rewrite::{self, FileEdits, RenamePreview};
+use crate::quick_open::{self, QuickOpenMatch};
+use crate::search_index::{self, IndexHit};
+use crate::tree_cache;
+use crate::tree_scan;
+use crate::tree_watcher;
+use crate::workspace::{self, Workspace, WorkspaceRoot};
+use crate::workspace_replace::{self, ReplacePreview, ReplaceSelection};
+use crate::workspace_search::{self, SearchOptions};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Mutex;
 use tauri::State;
 
 pub struct OpenedFile(pub Mutex<Option<String>>);
 pub struct CurrentRoot(pub Mutex<Option<String>>);
+/// Open multi-root workspace, if any; every member root is registered as ours.
+pub struct CurrentWorkspace(pub Mutex<Option<Workspace>>);
 
 #[derive(serde::Serialize)]
 pub struct FileContext {
     pub root: String,
     pub is_git: bool,
+    /// Repository layout (work tree, git dir, worktree/submodule) when `is_git`.
+    pub repo: Option<GitRepo>,
+    /// All repositories enclosing the file, innermost first (`repos[0] == repo`).
+    pub repos: Vec<GitRepo>,
+    /// Tree scanning rules applied to `root` and the files they came from.
+    pub config: LoadedConfig,
+    /// Set when `root` is an mdBook or MkDocs project ("book order" is available).
+    pub book: Option<BookKind>,
 }
 
+/// Resolve the root for `path`: the enclosing workspace member root if a
+/// workspace is open and contains it, else the git root or parent dir.
 #[tauri::command]
-pub fn get_context(path: String) -> Result<FileContext, String> {
+pub fn get_context(
+    path: String,
+    workspace: State<'_, CurrentWorkspace>,
+) -> Result<FileContext, String> {
     let p = PathBuf::from(&path);
-    let git_root = file_tree::detect_git_root(&p);
-    let is_git = git_root.is_some();
-    let root = file_tree::resolve_root(&p, git_root.clone());
+    let repos = git_repo::discover_chain(&p);
+    let repo = repos.first().cloned();
+    let is_git = repo.is_some();
+    let member_root = workspace
+        .0
+        .lock()
+        .ok()
+        .and_then(|ws| ws.as_ref()?.member_root(&p).map(|r| r.path.clone()));
+    let root = match member_root {
+        Some(root) => root,
+        None => file_tree::resolve_root(&p, repo.as_ref().map(|r| r.work_tree.clone())),
+    };
+    let config = config::load_for_root(&root);
+    let book = book_nav::detect(&root);
     Ok(FileContext {
         root: root.to_string_lossy().to_string(),
         is_git,
+        repo,
+        repos,
+        config,
+        book,
     })
 }
 
+/// Full (capped) tree for `root`, in book order when that sort is chosen and
+/// the root declares a navigation. Files come from the watcher's index when ready,
+/// else from the on-disk cache (reconciled in the background, with any
+/// difference emitted as `tree-changed`); otherwise scanned on a blocking
+/// thread with `tree-progress` events (sharing the watcher's cold-start scan
+/// when one runs), and fails with "Scan cancelled" if the current root
+/// switches meanwhile.
 #[tauri::command]
-pub fn get_file_tree(root: String) -> Result<Vec<FileNode>, String> {
+pub async fn get_file_tree(root: String, app: tauri::AppHandle) -> Result<Vec<FileNode>, String> {
     let p = PathBuf::from(&root);
     if !p.exists() {
         return Err(format!("Path does not exist: {}", root));
     }
-    Ok(file_tree::build_md_tree(&p))
+    tauri::async_runtime::spawn_blocking(move || {
+        let loaded = config::load_for_root(&p);
+        let files = match tree_watcher::cached_files(&p, &p) {
+            Some(files) => files,
+            None => match tree_cache::load(&app, &p, &loaded.config) {
+                Some(cached) => {
+                    let files = cached.files(&p);
+                    tree_cache::reconcile(p.clone(), cached, loaded.config.clone(), app.clone());
+                    files
+                }
+                None => tree_scan::scan(&p, &loaded.config, &app)
+                    .ok_or_else(|| "Scan cancelled".to_string())?,
+            },
+        };
+        let sort = loaded.config.tree_sort();
+        let nav = match sort.by {
+            SortKey::Book => book_nav::load(&p),
+            _ => None,
+        };
+        let mut tree = match nav {
+            Some(nav) => file_tree::assemble_book_tree(&p, &nav, &files, sort),
+            None => file_tree::assemble_tree(&p, &files, sort),
+        };
+        file_tree::limit_tree(&mut tree, None, loaded.config.max_nodes);
+        annotate_git_status(&p, &mut tree, &loaded.config);
+        Ok(tree)
+    })
+    .await
+    .map_err(|e| format!("Tree scan failed: {}", e))?
+}
+
+/// Children of `dir` down to `depth` levels (capped by `max_nodes`); deeper
+/// dirs come back `truncated`. Before the root's background index is ready
+/// this is a single-level listing of `dir`. In book order `dir` may also be a
+/// nav section's path, and both sections and dirs are looked up in the
+/// rebuilt book tree so pages the nav declares don't show up twice.
+#[tauri::command]
+pub async fn get_tree_children(
+    root: String,
+    dir: String,
+    depth: usize,
+) -> Result<Vec<FileNode>, String> {
+    let root_p = PathBuf::from(&root);
+    let dir_p = PathBuf::from(&dir);
+    if !dir_p.starts_with(&root_p) {
+        return Err(format!("{} is not inside {}", dir, root));
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let loaded = config::load_for_root(&root_p);
+        let sort = loaded.config.tree_sort();
+        let nav = match sort.by {
+            SortKey::Book => book_nav::load(&root_p),
+            _ => None,
+        };
+        let files = match (nav.is_some(), tree_watcher::cached_files(&root_p, &root_p)) {
+            (true, Some(files)) => Some(files),
+            // a section only exists in the book tree, so build it from a scan
+            (true, None) if dir_p != root_p => Some(file_tree::scan_md_files(
+                &root_p,
+                &loaded.config,
+                ScanScope::All,
+            )),
+            _ => None,
+        };
+        let mut nodes = match (nav, files) {
+            (Some(nav), Some(files)) => {
+                let tree = file_tree::assemble_book_tree(&root_p, &nav, &files, sort);
+                let mut nodes = if dir_p == root_p {
+                    tree
+                } else {
+                    file_tree::find_children(tree, &dir).unwrap_or_default()
+                };
+                file_tree::limit_tree(&mut nodes, Some(depth.max(1)), loaded.config.max_nodes);
+                nodes
+            }
+            _ => match tree_watcher::cached_files(&root_p, &dir_p) {
+                Some(files) => {
+                    let mut nodes = file_tree::subtree(&root_p, &dir_p, &files, sort);
+                    file_tree::limit_tree(&mut nodes, Some(depth.max(1)), loaded.config.max_nodes);
+                    nodes
+                }
+                None => file_tree::list_children(&root_p, &loaded.config, &dir_p),
+            },
+        };
+        annotate_git_status(&root_p, &mut nodes, &loaded.config);
+        Ok(nodes)
+    })
+    .await
+    .map_err(|e| format!("Tree listing failed: {}", e))?
+}
+
+/// Persist the sort chosen for `root` and return its updated config; the
+/// next `get_file_tree` / `get_tree_children` comes back in the new order.
+#[tauri::command]
+pub fn set_tree_sort(
+    root: String,
+    sort: SortOrder,
+    sort_by: SortKey,
+) -> Result<LoadedConfig, String> {
+    let root = PathBuf::from(root);
+    config::save_root_sort(&root, sort, sort_by)?;
+    Ok(config::load_for_root(&root))
+}
+
+fn annotate_git_status(p: &Path, tree: &mut [FileNode], config: &TreeConfig) {
+    if let Some(git_root) = file_tree::detect_git_root(p) {
+        // the outer repo doesn't report files inside nested repos; ask each one
+        let mut statuses = HashMap::new();
+        let repos = std::iter::once((p.to_path_buf(), git_root)).chain(
+            file_tree::nested_repo_roots(tree)
+                .into_iter()
+                .map(|r| (r.clone(), r)),
+        );
+        for (dir, repo_root) in repos {
+            match git_status::collect(&dir, &repo_root, config) {
+                Ok(s) => statuses.extend(s),
+                Err(e) => eprintln!("[mdcat] {}", e),
+            }
+        }
+        git_status::annotate(tree, &statuses);
+    }
 }
 
 #[tauri::command]
@@ -40,9 +218,40 @@ pub fn read_file(path: String) -> Result<String, String> {
     std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))
 }
 
+/// Sequence for `write_file`'s temp names, so concurrent saves of one file
+/// never share a temp file.
+static WRITE_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// Replace the file at `path` with `content` without ever leaving it half
+/// written: write a hidden sibling, flush it to disk, then rename it over the
+/// target. Symlinks are followed and the target's permissions kept.
+fn write_file(path: &Path, content: &str) -> std::io::Result<()> {
+    let target = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let name = target.file_name().unwrap_or_default().to_string_lossy();
+    let seq = WRITE_SEQ.fetch_add(1, Ordering::Relaxed);
+    let tmp = target.with_file_name(format!(
+        ".{}.{}-{}.mdcat-tmp",
+        name,
+        std::process::id(),
+        seq
+    ));
+    let written = std::fs::File::create(&tmp).and_then(|mut file| {
+        file.write_all(content.as_bytes())?;
+        if let Ok(meta) = std::fs::metadata(&target) {
+            file.set_permissions(meta.permissions())?;
+        }
+        file.sync_all()
+    });
+    written
+        .and_then(|()| std::fs::rename(&tmp, &target))
+        .inspect_err(|_| {
+            let _ = std::fs::remove_file(&tmp);
+        })
+}
+
 #[tauri::command]
 pub fn save_file(path: String, content: String) -> Result<(), String> {
-    std::fs::write(&path, &content).map_err(|e| format!("Failed to write {}: {}", path, e))
+    write_file(Path::new(&path), &content).map_err(|e| format!("Failed to write {}: {}", path, e))
 }
 
 #[tauri::command]
@@ -66,6 +275,105 @@ pub fn rename_file(old_path: String, new_path: String) -> Result<(), String> {
         .map_err(|e| format!("Failed to rename {} → {}: {}", old_path, new_path, e))
 }
 
+/// Link edits that moving `old_path` (a file or directory) to `new_path`
+/// would make across its root, without touching anything.
+#[tauri::command]
+pub async fn preview_rename(
+    old_path: String,
+    new_path: String,
+    current_root: State<'_, CurrentRoot>,
+    workspace: State<'_, CurrentWorkspace>,
+) -> Result<RenamePreview, String> {
+    let (from, to) = (PathBuf::from(old_path), PathBuf::from(new_path));
+    let (root, config) = root_for(&from, &current_root, &workspace);
+    tauri::async_runtime::spawn_blocking(move || {
+        let rewrites = link_rewrite::plan(&root, &config, &from, &to);
+        link_rewrite::preview(&root, &rewrites, &from, &to)
+    })
+    .await
+    .map_err(|e| format!("Rename preview failed: {}", e))
+}
+
+#[derive(serde::Serialize)]
+pub struct RenameOutcome {
+    /// Files whose links were rewritten, at their paths after the move.
+    pub written: Vec<String>,
+    /// Previewed files changed on disk since (or not in the preview); their
+    /// links were left as they are.
+    pub refused: Vec<String>,
+}
+
+/// Move `old_path` to `new_path` like `rename_file`, first rewriting the
+/// links of the `approved` files from `preview_rename` whose stamp still
+/// matches. Each file is written like `save_file`; if a write or the move
+/// fails, the files already written are restored and nothing is moved.
+#[tauri::command]
+pub async fn rename_with_links(
+    old_path: String,
+    new_path: String,
+    approved: Vec<FileEdits>,
+    current_root: State<'_, CurrentRoot>,
+    workspace: State<'_, CurrentWorkspace>,
+) -> Result<RenameOutcome, String> {
+    let (from, to) = (PathBuf::from(&old_path), PathBuf::from(&new_path));
+    let (root, config) = root_for(&from, &current_root, &workspace);
+    tauri::async_runtime::spawn_blocking(move || {
+        if to.exists() {
+            return Err(format!("{} already exists", new_path));
+        }
+        let stamps: HashMap<&str, &str> = approved
+            .iter()
+            .map(|f| (f.path.as_str(), f.stamp.as_str()))
+            .collect();
+        let mut outcome = RenameOutcome {
+            written: Vec::new(),
+            refused: Vec::new(),
+        };
+        let mut rewrites = Vec::new();
+        for rewrite in link_rewrite::plan(&root, &config, &from, &to) {
+            let path = rewrite.path.to_string_lossy().to_string();
+            if stamps.get(path.as_str()) == Some(&rewrite.stamp().as_str()) {
+                rewrites.push(rewrite);
+            } else {
+                outcome.refused.push(path);
+            }
+        }
+
+        let mut written: Vec<&link_rewrite::Rewrite> = Vec::new();
+        let mut result = Ok(());
+        for rewrite in &rewrites {
+            if let Err(e) = write_file(&rewrite.path, &rewrite.apply()) {
+                result = Err(format!(
+                    "Failed to rewrite links in {}: {}",
+                    rewrite.path.display(),
+                    e
+                ));
+                break;
+            }
+            written.push(rewrite);
+        }
+        if let Err(e) = result.and_then(|()| rename_file(old_path, new_path)) {
+            for rewrite in written {
+                if let Err(err) = write_file(&rewrite.path, &rewrite.content) {
+                    eprintln!(
+                        "[mdcat] Failed to restore {}: {}",
+                        rewrite.path.display(),
+                        err
+                    );
+                }
+            }
+            return Err(e);
+        }
+        outcome.written = rewrites
+            .iter()
+            .map(|r| r.new_path.to_string_lossy().to_string())
+            .collect();
+        Ok(outcome)
+    })
+    .await
+    .map_err(|e| format!("Rename failed: {}", e))?
+}
+
 #[tauri::command]
 pub fn delete_file(path: String) -> Result<(), String> {
     std::fs::remove_file(&path).map_err(|e| format!("Failed to delete {}: {}", path, e))
@@ -90,16 +398,27 @@ pub fn get_opened_file(state: State<'_, OpenedFile>) -> Option<String> {
 }
 
 #[tauri::command]
-pub fn set_current_root(root: String, state: State<'_, CurrentRoot>) {
-    // Unregister old root if any
+pub fn set_current_root(
+    root: String,
+    state: State<'_, CurrentRoot>,
+    workspace: State<'_, CurrentWorkspace>,
+) {
+    let members = workspace_roots(&workspace);
+
+    // Unregister old root if any (workspace members stay registered)
     if let Ok(lock) = state.0.lock() {
         if let Some(ref old_root) = *lock {
-            instance_registry::unregister_root(old_root);
+            if !members.iter().any(|m| m == Path::new(old_root)) {
+                instance_registry::unregister_root(old_root);
+            }
         }
     }
 
     // Register new root
     instance_registry::register_root(&root);
+    let mut keep = members;
+    keep.push(PathBuf::from(&root));
+    tree_scan::cancel_other_roots(&keep);
 
     // Update state
     if let Ok(mut lock) = state.0.lock() {
@@ -112,8 +431,133 @@ pub fn get_current_root(state: State<'_, CurrentRoot>) -> Option<String> {
     state.0.lock().ok()?.clone()
 }
 
+fn workspace_roots(workspace: &CurrentWorkspace) -> Vec<PathBuf> {
+    workspace
+        .0
+        .lock()
+        .ok()
+        .and_then(|ws| ws.as_ref().map(Workspace::root_paths))
+        .unwrap_or_default()
+}
+
+/// Roots searched by window-wide lookups: every workspace root, else the
+/// current root, each with its tree config.
+fn open_roots(
+    current_root: &CurrentRoot,
+    workspace: &CurrentWorkspace,
+) -> Vec<(PathBuf, TreeConfig)> {
+    let mut roots = workspace_roots(workspace);
+    if roots.is_empty() {
+        roots.extend(
+            current_root
+                .0
+                .lock()
+                .ok()
+                .and_then(|r| r.clone())
+                .map(PathBuf::from),
+        );
+    }
+    roots
+        .into_iter()
+        .map(|root| {
+            let config = config::load_for_root(&root).config;
+            (root, config)
+        })
+        .collect()
+}
+
+/// The open root containing `path` (the innermost, for nested workspace
+/// roots), else the root `get_context` would pick for it.
+fn root_for(
+    path: &Path,
+    current_root: &CurrentRoot,
+    workspace: &CurrentWorkspace,
+) -> (PathBuf, TreeConfig) {
+    let open = open_roots(current_root, workspace)
+        .into_iter()
+        .filter(|(root, _)| path.starts_with(root))
+        .max_by_key(|(root, _)| root.components().count());
+    open.unwrap_or_else(|| {
+        let repo = git_repo::discover(path);
+        let root = file_tree::resolve_root(path, repo.map(|r| r.work_tree));
+        let config = config::load_for_root(&root).config;
+        (root, config)
+    })
+}
+
+/// Make `ws` the open workspace: register all its roots so files opened from
+/// any member repo route to this window, and release the previous one's.
+fn set_workspace(
+    ws: Option<Workspace>,
+    workspace: &CurrentWorkspace,
+    current_root: &CurrentRoot,
+) -> Result<(), String> {
+    let current = current_root.0.lock().ok().and_then(|r| r.clone());
+    let mut lock = workspace.0.lock().map_err(|e| e.to_string())?;
+    if let Some(old) = lock.as_ref() {
+        for root in &old.roots {
+            let root = root.path.to_string_lossy();
+            if current.as_deref() != Some(root.as_ref()) {
+                instance_registry::unregister_root(&root);
+            }
+        }
+    }
+    if let Some(new) = ws.as_ref() {
+        for root in &new.roots {
+            instance_registry::register_root(&root.path.to_string_lossy());
+        }
+    }
+    *lock = ws;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn open_workspace(
+    path: String,
+    workspace: State<'_, CurrentWorkspace>,
+    current_root: State<'_, CurrentRoot>,
+) -> Result<Workspace, String> {
+    let ws = workspace::load(Path::new(&path))?;
+    set_workspace(Some(ws.clone()), &workspace, &current_root)?;
+    Ok(ws)
+}
+
+/// Write `roots` to the workspace file at `path` and open it.
+#[tauri::command]
+pub fn save_workspace(
+    path: String,
+    name: Option<String>,
+    roots: Vec<WorkspaceRoot>,
+    workspace: State<'_, CurrentWorkspace>,
+    current_root: State<'_, CurrentRoot>,
+) -> Result<Workspace, String> {
+    let mut path = PathBuf::from(path);
+    if !workspace::is_workspace_file(&path) {
+        path.set_extension(workspace::WORKSPACE_EXTENSION);
+    }
+    let ws = workspace::save(&path, name, &roots)?;
+    set_workspace(Some(ws.clone()), &workspace, &current_root)?;
+    Ok(ws)
+}
+
+#[tauri::command]
+pub fn close_workspace(
+    workspace: State<'_, CurrentWorkspace>,
+    current_root: State<'_, CurrentRoot>,
+) -> Result<(), String> {
+    set_workspace(None, &workspace, &current_root)
+}
+
+#[tauri::command]
+pub fn get_workspace(workspace: State<'_, CurrentWorkspace>) -> Option<Workspace> {
+    workspace.0.lock().ok()?.clone()
+}
+
+/// Watch the file shown in the window; also records it as recently opened
+/// for `quick_open`.
 #[tauri::command]
 pub fn watch_file(path: String, app: tauri::AppHandle) -> Result<(), String> {
+    quick_open::record_open(&app, Path::new(&path));
     file_watcher::watch(&path, app)
 }
 
@@ -122,6 +566,325 @@ pub fn unwatch_file() {
     file_watcher::unwatch();
 }
 
+/// Watch `roots` recursively (one per workspace section); emits
+/// `tree-changed` with added/removed/renamed paths.
+#[tauri::command]
+pub fn watch_tree(roots: Vec<String>, app: tauri::AppHandle) -> Result<(), String> {
+    let roots: Vec<PathBuf> = roots.into_iter().map(PathBuf::from).collect();
+    search_index::retain(&app, &roots);
+    heading_index::retain(&roots);
+    link_graph::retain(&roots);
+    tree_watcher::watch(&roots, app)
+}
+
+#[tauri::command]
+pub fn unwatch_tree() {
+    tree_watcher::unwatch();
+}
+
+/// Search every markdown file under `roots` for `query`. Results arrive as
+/// `search-results` events tagged with the returned id; a new search (or
+/// `cancel_search`) cancels the running one.
+#[tauri::command]
+pub fn search_workspace(
+    roots: Vec<String>,
+    query: String,
+    options: SearchOptions,
+    app: tauri::AppHandle,
+) -> Result<u64, String> {
+    let roots = roots
+        .into_iter()
+        .map(|root| {
+            let root = PathBuf::from(root);
+            let config = config::load_for_root(&root).config;
+            (root, config)
+        })
+        .collect();
+    workspace_search::start(roots, &query, options, app)
+}
+
+#[tauri::command]
+pub fn cancel_search() {
+    workspace_search::cancel();
+}
+
+/// Fuzzy-match `query` against the paths and titles of every markdown file
+/// in the open workspace (or the current root), recently opened files first
+/// among equals.
+#[tauri::command]
+pub async fn quick_open(
+    query: String,
+    limit: Option<usize>,
+    app: tauri::AppHandle,
+    current_root: State<'_, CurrentRoot>,
+    workspace: State<'_, CurrentWorkspace>,
+) -> Result<Vec<QuickOpenMatch>, String> {
+    let roots = open_roots(&current_root, &workspace);
+    tauri::async_runtime::spawn_blocking(move || {
+        quick_open::search(
+            &app,
+            &roots,
+            &query,
+            limit.unwrap_or(quick_open::DEFAULT_LIMIT),
+        )
+    })
+    .await
+    .map_err(|e| format!("Quick open failed: {}", e))
+}
+
+/// Headings anywhere in the open workspace (or the current root) that
+/// fuzzy-match `query`, each with its file, slug and line.
+#[tauri::command]
+pub async fn search_headings(
+    query: String,
+    limit: Option<usize>,
+    current_root: State<'_, CurrentRoot>,
+    workspace: State<'_, CurrentWorkspace>,
+) -> Result<Vec<HeadingMatch>, String> {
+    let roots = open_roots(&current_root, &workspace);
+    tauri::async_runtime::spawn_blocking(move || {
+        heading_index::search(
+            &roots,
+            &query,
+            limit.unwrap_or(heading_index::DEFAULT_LIMIT),
+        )
+    })
+    .await
+    .map_err(|e| format!("Heading search failed: {}", e))
+}
+
+/// Headings of the file at `path`, in document order.
+#[tauri::command]
+pub fn get_outline(path: String) -> Result<Vec<Heading>, String> {
+    heading_index::outline(Path::new(&path))
+}
+
+/// Links in other files of `path`'s root that point at it, built on first
+/// use and kept current by the tree watcher.
+#[tauri::command]
+pub async fn get_backlinks(
+    path: String,
+    current_root: State<'_, CurrentRoot>,
+    workspace: State<'_, CurrentWorkspace>,
+) -> Result<Vec<Backlink>, String> {
+    let path = PathBuf::from(path);
+    let (root, config) = root_for(&path, &current_root, &workspace);
+    tauri::async_runtime::spawn_blocking(move || link_graph::backlinks(&root, &config, &path))
+        .await
+        .map_err(|e| format!("Backlinks failed: {}", e))
+}
+
+/// Links in the file at `path`, each resolved against its root.
+#[tauri::command]
+pub async fn get_outgoing_links(
+    path: String,
+    current_root: State<'_, CurrentRoot>,
+    workspace: State<'_, CurrentWorkspace>,
+) -> Result<Vec<Link>, String> {
+    let path = PathBuf::from(path);
+    let (root, config) = root_for(&path, &current_root, &workspace);
+    tauri::async_runtime::spawn_blocking(move || link_graph::outgoing(&path, &root, &config))
+        .await
+        .map_err(|e| format!("Outgoing links failed: {}", e))?
+}
+
+/// Where the wiki link `text` (`[[page#heading|label]]`) in `from_path`
+/// leads: the file by stem, alias or path suffix within its root, plus
+/// every candidate when the name is ambiguous.
+#[tauri::command]
+pub async fn resolve_wikilink(
+    from_path: String,
+    text: String,
+    current_root: State<'_, CurrentRoot>,
+    workspace: State<'_, CurrentWorkspace>,
+) -> Result<WikiResolution, String> {
+    let from = PathBuf::from(from_path);
+    let (root, config) = root_for(&from, &current_root, &workspace);
+    tauri::async_runtime::spawn_blocking(move || {
+        link_graph::resolve_wikilink(&root, &config, &from, &text)
+    })
+    .await
+    .map_err(|e| format!("Wiki link resolution failed: {}", e))
+}
+
+/// Destinations for a link being typed in `from_path`: files and assets of
+/// its root as relative paths, or after a `#`, the target's heading slugs.
+/// Nearby and recently opened files rank first among equals.
+#[tauri::command]
+pub async fn complete_link(
+    from_path: String,
+    prefix: String,
+    limit: Option<usize>,
+    app: tauri::AppHandle,
+    current_root: State<'_, CurrentRoot>,
+    workspace: State<'_, CurrentWorkspace>,
+) -> Result<Vec<LinkCompletion>, String> {
+    let from = PathBuf::from(from_path);
+    let (root, config) = root_for(&from, &current_root, &workspace);
+    tauri::async_runtime::spawn_blocking(move || {
+        let limit = limit.unwrap_or(link_complete::DEFAULT_LIMIT);
+        link_complete::complete(&app, &root, &config, &from, &prefix, limit)
+    })
+    .await
+    .map_err(|e| format!("Link completion failed: {}", e))
+}
+
+/// Broken relative links and `#anchors` in every markdown file under `root`.
+#[tauri::command]
+pub async fn check_links(root: String) -> Result<LinkReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = PathBuf::from(root);
+        let config = config::load_for_root(&root).config;
+        link_check::check(&root, &config)
+    })
+    .await
+    .map_err(|e| format!("Link check failed: {}", e))
+}
+
+/// Assets under `root` that no markdown file or site file references, and
+/// references to assets that don't exist.
+#[tauri::command]
+pub async fn get_asset_report(root: String) -> Result<AssetReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = PathBuf::from(root);
+        let config = config::load_for_root(&root).config;
+        asset_report::report(&root, &config)
+    })
+    .await
+    .map_err(|e| format!("Asset report failed: {}", e))
+}
+
+/// Move the given assets of `root` to the app's trash folder; any that are
+/// referenced again since the report are refused.
+#[tauri::command]
+pub async fn trash_unused_assets(
+    root: String,
+    paths: Vec<String>,
+    app: tauri::AppHandle,
+) -> Result<TrashOutcome, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = PathBuf::from(root);
+        let config = config::load_for_root(&root).config;
+        asset_report::trash_unused(&app, &root, &config, &paths)
+    })
+    .await
+    .map_err(|e| format!("Trashing assets failed: {}", e))?
+}
+
+/// Assets trashed by `trash_unused_assets` that can still be put back,
+/// newest trashing first.
+#[tauri::command]
+pub fn list_trash(app: tauri::AppHandle) -> Result<Vec<TrashEntry>, String> {
+    asset_report::list_trash(&app)
+}
+
+/// Put `files` of the trashing `id` (as listed by `list_trash`) back under
+/// their root; files whose original path is taken again are refused.
+#[tauri::command]
+pub async fn restore_trashed(
+    id: String,
+    files: Vec<String>,
+    app: tauri::AppHandle,
+) -> Result<RestoreOutcome, String> {
+    tauri::async_runtime::spawn_blocking(move || asset_report::restore_trashed(&app, &id, &files))
+        .await
+        .map_err(|e| format!("Restoring assets failed: {}", e))?
+}
+
+/// Top `limit` ranked hits for `query` across `roots` from the persistent
+/// full-text index, built on first use. Supports `"phrases"` and `prefix*`.
+#[tauri::command]
+pub async fn ranked_search(
+    roots: Vec<String>,
+    query: String,
+    limit: Option<usize>,
+    app: tauri::AppHandle,
+) -> Result<Vec<IndexHit>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let roots: Vec<(PathBuf, TreeConfig)> = roots
+            .into_iter()
+            .map(|root| {
+                let root = PathBuf::from(root);
+                let config = config::load_for_root(&root).config;
+                (root, config)
+            })
+            .collect();
+        search_index::search(
+            &app,
+            &roots,
+            &query,
+            limit.unwrap_or(search_index::DEFAULT_LIMIT),
+        )
+    })
+    .await
+    .map_err(|e| format!("Search failed: {}", e))?
+}
+
+/// Every match of `query` in the open workspace (or the current root) with
+/// the line before and after replacing it with `replacement`. In regex mode
+/// `$1` / `${name}` in `replacement` expand to capture groups.
+#[tauri::command]
+pub async fn preview_replace(
+    query: String,
+    replacement: String,
+    options: SearchOptions,
+    current_root: State<'_, CurrentRoot>,
+    workspace: State<'_, CurrentWorkspace>,
+) -> Result<ReplacePreview, String> {
+    let roots = open_roots(&current_root, &workspace);
+    tauri::async_runtime::spawn_blocking(move || {
+        workspace_replace::preview(&roots, &query, &replacement, options)
+    })
+    .await
+    .map_err(|e| format!("Replace preview failed: {}", e))?
+}
+
+#[derive(serde::Serialize)]
+pub struct ReplaceOutcome {
+    /// Files written and replacements made in them.
+    pub written: Vec<String>,
+    pub replaced: usize,
+    /// Files changed on disk since the preview, left untouched.
+    pub refused: Vec<String>,
+}
+
+/// Apply the selected edits of a `preview_replace` with the same `query`,
+/// `replacement` and `options`. Each file is written like `save_file`;
+/// files whose content no longer matches the preview are refused. If a
+/// write fails, the files already written are restored and nothing changes.
+#[tauri::command]
+pub async fn apply_replace(
+    query: String,
+    replacement: String,
+    options: SearchOptions,
+    selections: Vec<ReplaceSelection>,
+) -> Result<ReplaceOutcome, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let plan = workspace_replace::plan(&query, &replacement, options, &selections)?;
+        for (i, write) in plan.writes.iter().enumerate() {
+            if let Err(e) = write_file(&write.path, &write.updated) {
+                for done in &plan.writes[..i] {
+                    if let Err(err) = write_file(&done.path, &done.original) {
+                        eprintln!("[mdcat] Failed to restore {}: {}", done.path.display(), err);
+                    }
+                }
+                return Err(format!("Failed to write {}: {}", write.path.display(), e));
+            }
+        }
+        Ok(ReplaceOutcome {
+            written: plan
+                .writes
+                .iter()
+                .map(|w| w.path.to_string_lossy().to_string())
+                .collect(),
+            replaced: plan.replacements,
+            refused: plan.refused,
+        })
+    })
+    .await
+    .map_err(|e| format!("Replace failed: {}", e))?
+}
+
 #[tauri::command]
 pub fn dump_state_to_file(state_json: String) -> Result<(), String> {
     let dir = std::env::temp_dir();
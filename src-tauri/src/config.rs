@@ -0,0 +1,347 @@
+//! Tree scanning rules: built-in defaults, overridden by a user-level config
+//! in the XDG config dir, overridden by `.mdcat.toml` at the repo root.
+//! Sort choices made in the UI are stored per root in `roots.toml` next to
+//! the user config and win over both.
+//!
+//! ```toml
+//! extensions = ["md", "markdown", "mdx"]
+//! include = ["docs/**"]
+//! exclude = ["**/CHANGELOG.md", "vendor/**"]
+//! skip_dirs = ["node_modules", "target"]
+//! show_hidden_dirs = [".github", ".claude", ".cursor", ".changeset", ".obsidian"]
+//! sort = "dirs-first"
+//! sort_by = "natural"
+//! max_nodes = 5000
+//! ```
+
+use crate::tree_cache;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+pub const REPO_CONFIG_FILENAME: &str = ".mdcat.toml";
+const USER_CONFIG_FILENAME: &str = "config.toml";
+const ROOT_PREFS_FILENAME: &str = "roots.toml";
+
+/// Dot-directories scanned by default: where repos keep templates, agent
+/// commands/rules and changesets.
+pub const DEFAULT_HIDDEN_DIRS: &[&str] = &[".github", ".claude", ".cursor", ".changeset"];
+
+/// Cursor keeps its rules as `.cursor/rules/*.mdc`, markdown under its own
+/// extension; inside a shown `.cursor` dir that extension counts as markdown.
+pub const CURSOR_DIR: &str = ".cursor";
+pub const CURSOR_RULES_EXT: &str = "mdc";
+
+/// How directories and files are grouped among siblings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortOrder {
+    /// Directories before files.
+    DirsFirst,
+    /// Files before directories.
+    FilesFirst,
+    /// Directories and files interleaved.
+    Mixed,
+}
+
+/// What siblings are ordered by within a group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortKey {
+    /// Name, case-insensitive, with digit runs compared numerically (`2-setup` < `10-deploy`).
+    Natural,
+    /// Name, case-insensitive, character by character.
+    Name,
+    /// Newest first; a directory counts as its newest file.
+    Modified,
+    /// Document title (frontmatter or first H1), falling back to the name.
+    Title,
+    /// Declared navigation of an mdBook or MkDocs project (see `book_nav`);
+    /// natural order where a root has none.
+    Book,
+}
+
+/// On-disk shape of a config file. Every key is optional so that a repo
+/// config only overrides what it mentions.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    extensions: Option<Vec<String>>,
+    include: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    skip_dirs: Option<Vec<String>>,
+    show_hidden_dirs: Option<Vec<String>>,
+    sort: Option<SortOrder>,
+    sort_by: Option<SortKey>,
+    max_nodes: Option<usize>,
+}
+
+/// Sort choices for one root, as stored in `roots.toml`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RootPrefs {
+    sort: Option<SortOrder>,
+    sort_by: Option<SortKey>,
+}
+
+/// Effective scanning rules for one root.
+#[derive(Debug, Clone, Serialize)]
+pub struct TreeConfig {
+    /// File extensions (without dot), matched case-insensitively.
+    pub extensions: Vec<String>,
+    /// Globs relative to the root; when non-empty, only matching files are shown.
+    pub include: Vec<String>,
+    /// Globs relative to the root; matching files and directories are skipped.
+    pub exclude: Vec<String>,
+    /// Directory names that are never descended into.
+    pub skip_dirs: Vec<String>,
+    /// Dot-directory names that are scanned despite being hidden.
+    pub show_hidden_dirs: Vec<String>,
+    pub sort: SortOrder,
+    pub sort_by: SortKey,
+    /// Hard cap on nodes returned in one tree response; deeper dirs load on expand.
+    pub max_nodes: usize,
+}
+
+impl Default for TreeConfig {
+    fn default() -> Self {
+        TreeConfig {
+            extensions: vec!["md".into(), "markdown".into()],
+            include: Vec::new(),
+            exclude: Vec::new(),
+            skip_dirs: vec!["node_modules".into(), "target".into()],
+            show_hidden_dirs: DEFAULT_HIDDEN_DIRS.iter().map(|d| d.to_string()).collect(),
+            sort: SortOrder::DirsFirst,
+            sort_by: SortKey::Natural,
+            max_nodes: 5000,
+        }
+    }
+}
+
+impl TreeConfig {
+    fn apply(&mut self, file: ConfigFile) {
+        if let Some(v) = file.extensions {
+            self.extensions = v
+                .into_iter()
+                .map(|e| e.trim_start_matches('.').to_string())
+                .collect();
+        }
+        if let Some(v) = file.include {
+            self.include = v;
+        }
+        if let Some(v) = file.exclude {
+            self.exclude = v;
+        }
+        if let Some(v) = file.skip_dirs {
+            self.skip_dirs = v;
+        }
+        if let Some(v) = file.show_hidden_dirs {
+            self.show_hidden_dirs = v;
+        }
+        if let Some(v) = file.sort {
+            self.sort = v;
+        }
+        if let Some(v) = file.sort_by {
+            self.sort_by = v;
+        }
+        if let Some(v) = file.max_nodes {
+            self.max_nodes = v;
+        }
+    }
+
+    /// Sibling ordering for the tree builder.
+    pub fn tree_sort(&self) -> TreeSort {
+        TreeSort {
+            order: self.sort,
+            by: self.sort_by,
+        }
+    }
+
+    pub fn has_markdown_ext(&self, path: &Path) -> bool {
+        let Some(ext) = path.extension() else {
+            return false;
+        };
+        let ext = ext.to_string_lossy();
+        if self.extensions.iter().any(|e| e.eq_ignore_ascii_case(&ext)) {
+            return true;
+        }
+        ext.eq_ignore_ascii_case(CURSOR_RULES_EXT)
+            && self.shows_cursor_rules()
+            && path.components().any(|c| c.as_os_str() == CURSOR_DIR)
+    }
+
+    /// Whether `.mdc` rules under `.cursor` are listed (see `CURSOR_DIR`).
+    pub fn shows_cursor_rules(&self) -> bool {
+        self.show_hidden_dirs.iter().any(|d| d == CURSOR_DIR)
+    }
+
+    /// Whether a directory with this name should be descended into.
+    pub fn allows_dir_name(&self, name: &str) -> bool {
+        if self.skip_dirs.iter().any(|d| d == name) {
+            return false;
+        }
+        !name.starts_with('.') || self.show_hidden_dirs.iter().any(|d| d == name)
+    }
+
+    /// Compile include/exclude globs. Invalid patterns are reported and dropped.
+    pub fn matcher(&self) -> PathMatcher {
+        PathMatcher {
+            include: compile_globs(&self.include),
+            exclude: compile_globs(&self.exclude),
+        }
+    }
+}
+
+/// Compiled include/exclude globs, matched against root-relative paths.
+pub struct PathMatcher {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl PathMatcher {
+    pub fn is_excluded(&self, rel: &Path) -> bool {
+        self.exclude.as_ref().is_some_and(|set| set.is_match(rel))
+    }
+
+    pub fn is_included(&self, rel: &Path) -> bool {
+        self.include.as_ref().is_none_or(|set| set.is_match(rel))
+    }
+}
+
+fn compile_globs(patterns: &[String]) -> Option<GlobSet> {
+    if patterns.is_empty() {
+        return None;
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        match Glob::new(pattern) {
+            Ok(glob) => {
+                builder.add(glob);
+            }
+            Err(e) => eprintln!("[mdcat] ignoring invalid glob {:?}: {}", pattern, e),
+        }
+    }
+    builder.build().ok()
+}
+
+/// Grouping plus key, as applied by `file_tree`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeSort {
+    pub order: SortOrder,
+    pub by: SortKey,
+}
+
+/// Result of resolving config for a root, including where it came from.
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadedConfig {
+    pub config: TreeConfig,
+    /// Config files that were applied, lowest precedence first.
+    pub sources: Vec<String>,
+    /// Parse errors for config files that were found but skipped.
+    pub errors: Vec<String>,
+}
+
+/// `$XDG_CONFIG_HOME/mdcat/config.toml`, falling back to `~/.config/mdcat/config.toml`.
+pub fn user_config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(base.join("mdcat").join(USER_CONFIG_FILENAME))
+}
+
+/// Resolve the effective tree config for `root`.
+pub fn load_for_root(root: &Path) -> LoadedConfig {
+    let mut loaded = LoadedConfig {
+        config: TreeConfig::default(),
+        sources: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    let candidates = user_config_path()
+        .into_iter()
+        .chain(std::iter::once(root.join(REPO_CONFIG_FILENAME)));
+
+    for path in candidates {
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+        match toml::from_str::<ConfigFile>(&contents) {
+            Ok(file) => {
+                loaded.config.apply(file);
+                loaded.sources.push(path.to_string_lossy().to_string());
+            }
+            Err(e) => {
+                let msg = format!("{}: {}", path.display(), e);
+                eprintln!("[mdcat] invalid config {}", msg);
+                loaded.errors.push(msg);
+            }
+        }
+    }
+
+    if let Some(prefs_path) = root_prefs_path() {
+        let key = root_key(root);
+        match read_root_prefs(&prefs_path) {
+            Ok(mut prefs) => {
+                if let Some(prefs) = prefs.remove(&key) {
+                    if let Some(v) = prefs.sort {
+                        loaded.config.sort = v;
+                    }
+                    if let Some(v) = prefs.sort_by {
+                        loaded.config.sort_by = v;
+                    }
+                    loaded
+                        .sources
+                        .push(format!("{} [{}]", prefs_path.display(), key));
+                }
+            }
+            Err(msg) => {
+                eprintln!("[mdcat] invalid config {}", msg);
+                loaded.errors.push(msg);
+            }
+        }
+    }
+
+    loaded
+}
+
+fn root_prefs_path() -> Option<PathBuf> {
+    Some(user_config_path()?.with_file_name(ROOT_PREFS_FILENAME))
+}
+
+fn root_key(root: &Path) -> String {
+    root.canonicalize()
+        .unwrap_or_else(|_| root.to_path_buf())
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Saved per-root prefs; empty when the file doesn't exist yet. A file that
+/// can't be read or parsed is an error, so saving never overwrites it.
+fn read_root_prefs(path: &Path) -> Result<BTreeMap<String, RootPrefs>, String> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+    toml::from_str(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Persist the sort chosen for `root` in the UI.
+pub fn save_root_sort(root: &Path, sort: SortOrder, sort_by: SortKey) -> Result<(), String> {
+    let path = root_prefs_path().ok_or("No config directory")?;
+    let mut prefs = read_root_prefs(&path)?;
+    prefs.insert(
+        root_key(root),
+        RootPrefs {
+            sort: Some(sort),
+            sort_by: Some(sort_by),
+        },
+    );
+    let contents =
+        toml::to_string_pretty(&prefs).map_err(|e| format!("Failed to encode prefs: {}", e))?;
+    tree_cache::write_entry(&path, contents.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
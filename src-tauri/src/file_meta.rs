@@ -0,0 +1,266 @@
+//! Per-file metadata for the tree: mtime, size, title, word count, task counts
+//! and top-level frontmatter scalars. Cached by path and revalidated by
+//! mtime + size, so rebuilding the tree only re-reads files that changed.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+static CACHE: Mutex<Option<HashMap<PathBuf, FileMeta>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FileMeta {
+    /// Modification time, milliseconds since the Unix epoch.
+    pub mtime_ms: u64,
+    pub size: u64,
+    /// Frontmatter `title`, else the first H1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub word_count: usize,
+    pub tasks_open: usize,
+    pub tasks_done: usize,
+    /// Top-level scalar `key: value` pairs from YAML frontmatter.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub frontmatter: BTreeMap<String, String>,
+}
+
+/// `(mtime_ms, size)` of `path`, without reading it.
+pub fn stat(path: &Path) -> Option<(u64, u64)> {
+    let stat = std::fs::metadata(path).ok()?;
+    let mtime_ms = stat
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    Some((mtime_ms, stat.len()))
+}
+
+/// Metadata for `path`, from cache when mtime and size are unchanged.
+pub fn get(path: &Path) -> Option<FileMeta> {
+    let (mtime_ms, size) = stat(path)?;
+
+    if let Ok(lock) = CACHE.lock() {
+        if let Some(meta) = lock.as_ref().and_then(|c| c.get(path)) {
+            if meta.mtime_ms == mtime_ms && meta.size == size {
+                return Some(meta.clone());
+            }
+        }
+    }
+
+    let content = std::fs::read_to_string(path).ok()?;
+    let mut meta = parse(&content);
+    meta.mtime_ms = mtime_ms;
+    meta.size = size;
+
+    if let Ok(mut lock) = CACHE.lock() {
+        lock.get_or_insert_with(HashMap::new)
+            .insert(path.to_path_buf(), meta.clone());
+    }
+    Some(meta)
+}
+
+/// Drop cached entries for files that no longer exist in the tree, returning
+/// what was known about them.
+pub fn forget(path: &Path) -> Option<FileMeta> {
+    CACHE.lock().ok()?.as_mut()?.remove(path)
+}
+
+/// Split a leading `---` YAML block off `content`.
+/// Returns `(frontmatter, body, body_start_line)`; line numbers are 0-based.
+pub fn split_frontmatter(content: &str) -> (Option<&str>, &str, usize) {
+    let rest = match content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    {
+        Some(r) => r,
+        None => return (None, content, 0),
+    };
+
+    let mut offset = 0;
+    for (i, line) in rest.split_inclusive('\n').enumerate() {
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            let fm = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            return (Some(fm), body, i + 2);
+        }
+        offset += line.len();
+    }
+    (None, content, 0)
+}
+
+/// Top-level `key: value` scalars; nested maps and lists are skipped.
+pub fn parse_frontmatter(fm: &str) -> BTreeMap<String, String> {
+    let mut out = BTreeMap::new();
+    for line in fm.lines() {
+        if line.starts_with(char::is_whitespace) || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = unquote(value.trim());
+        if key.is_empty() || value.is_empty() || value.starts_with('[') || value.starts_with('{') {
+            continue;
+        }
+        out.insert(key.trim().to_string(), value.to_string());
+    }
+    out
+}
+
+/// Values of a top-level list key, written inline (`key: [a, "b"]`), as a
+/// block (`key:` then `- a` lines) or as a single scalar (`key: a`).
+pub fn frontmatter_list(fm: &str, key: &str) -> Vec<String> {
+    let mut lines = fm.lines();
+    let Some(value) = lines.by_ref().find_map(|line| {
+        let (k, v) = line.split_once(':')?;
+        (k == key).then(|| v.trim())
+    }) else {
+        return Vec::new();
+    };
+    let items: Vec<&str> = if let Some(inline) = value.strip_prefix('[') {
+        inline.trim_end_matches(']').split(',').collect()
+    } else if value.is_empty() {
+        lines
+            .map_while(|line| line.trim_start().strip_prefix("- "))
+            .collect()
+    } else {
+        vec![value]
+    };
+    items
+        .into_iter()
+        .map(|item| unquote(item.trim()).to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn unquote(value: &str) -> &str {
+    for q in ['"', '\''] {
+        if value.len() >= 2 && value.starts_with(q) && value.ends_with(q) {
+            return &value[1..value.len() - 1];
+        }
+    }
+    value
+}
+
+/// Tracks fenced code blocks while scanning lines, so headings and task
+/// markers inside ``` / ~~~ fences are ignored.
+#[derive(Default)]
+pub struct FenceState {
+    fence: Option<(char, usize)>,
+}
+
+impl FenceState {
+    /// Feed one line; returns true if the line is part of a fenced block
+    /// (including the fence lines themselves).
+    pub fn is_code(&mut self, line: &str) -> bool {
+        let trimmed = line.trim_start();
+        let marker = trimmed.chars().next();
+        if let Some(ch @ ('`' | '~')) = marker {
+            let len = trimmed.chars().take_while(|c| *c == ch).count();
+            if len >= 3 {
+                match self.fence {
+                    None => self.fence = Some((ch, len)),
+                    Some((open, open_len)) if open == ch && len >= open_len => self.fence = None,
+                    _ => {}
+                }
+                return true;
+            }
+        }
+        self.fence.is_some()
+    }
+}
+
+/// ATX heading text and level (`## Title ##` -> (2, "Title")).
+pub fn atx_heading(line: &str) -> Option<(u8, &str)> {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    let rest = &trimmed[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    // a closing `#` run only counts after a space or tab: `# Learn C#` keeps it
+    let text = rest.trim();
+    let unclosed = text.trim_end_matches('#');
+    let text = if unclosed.is_empty() || unclosed.ends_with([' ', '\t']) {
+        unclosed.trim_end()
+    } else {
+        text
+    };
+    Some((level as u8, text))
+}
+
+fn task_marker(line: &str) -> Option<bool> {
+    let trimmed = line.trim_start();
+    let after_bullet = if let Some(r) = trimmed.strip_prefix(['-', '*', '+']) {
+        r
+    } else {
+        let digits = trimmed.chars().take_while(|c| c.is_ascii_digit()).count();
+        if digits == 0 {
+            return None;
+        }
+        trimmed[digits..].strip_prefix(['.', ')'])?
+    };
+    let rest = after_bullet.strip_prefix(' ')?.trim_start();
+    match rest.get(..3)? {
+        "[ ]" => Some(false),
+        "[x]" | "[X]" => Some(true),
+        _ => None,
+    }
+}
+
+/// Compute content-derived fields (everything except mtime and size).
+pub fn parse(content: &str) -> FileMeta {
+    let (fm, body, _) = split_frontmatter(content);
+    let frontmatter = fm.map(parse_frontmatter).unwrap_or_default();
+
+    let mut h1: Option<String> = None;
+    let mut word_count = 0;
+    let mut tasks_open = 0;
+    let mut tasks_done = 0;
+    let mut fences = FenceState::default();
+    let mut prev_line: Option<&str> = None;
+
+    for line in body.lines() {
+        if fences.is_code(line) {
+            prev_line = None;
+            continue;
+        }
+        word_count += line.split_whitespace().count();
+        if h1.is_none() {
+            if let Some((1, text)) = atx_heading(line) {
+                h1 = Some(text.to_string());
+            } else if let Some(prev) = prev_line {
+                // setext: "Title\n====="
+                let t = line.trim();
+                if !t.is_empty() && t.chars().all(|c| c == '=') && !prev.trim().is_empty() {
+                    h1 = Some(prev.trim().to_string());
+                }
+            }
+        }
+        match task_marker(line) {
+            Some(true) => tasks_done += 1,
+            Some(false) => tasks_open += 1,
+            None => {}
+        }
+        prev_line = Some(line);
+    }
+
+    FileMeta {
+        title: frontmatter.get("title").cloned().or(h1),
+        word_count,
+        tasks_open,
+        tasks_done,
+        frontmatter,
+        ..FileMeta::default()
+    }
+}
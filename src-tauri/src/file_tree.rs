@@ -1,39 +1,71 @@
+use crate::book_nav::NavItem;
+use crate::config::{PathMatcher, SortKey, SortOrder, TreeConfig, TreeSort, REPO_CONFIG_FILENAME};
+use crate::file_meta::{self, FileMeta};
+use crate::git_repo::{self, RepoKind};
+use crate::git_status::GitStatus;
+use ignore::{WalkBuilder, WalkState};
 use serde::Serialize;
-use std::collections::HashMap;
-use std::path::{Path, PathBuf};
-use std::process::Command;
-use walkdir::WalkDir;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Project-level ignore file, same syntax as `.gitignore`.
+/// Honored in non-git roots too.
+pub const MDCAT_IGNORE_FILENAME: &str = ".mdcatignore";
+
+/// Extensions of files markdown links and embeds typically point at
+/// (images, documents, media), matched case-insensitively.
+pub const ASSET_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "svg", "webp", "avif", "bmp", "ico", "tif", "tiff", "pdf", "mp4",
+    "webm", "mov", "mp3", "wav", "ogg", "m4a",
+];
 
 #[derive(Debug, Clone, Serialize)]
 pub struct FileNode {
     pub name: String,
     pub path: String,
     pub is_dir: bool,
+    /// Name starts with `.` or lives inside a dot-directory.
+    pub is_hidden: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub children: Option<Vec<FileNode>>,
+    /// Dirs only: children were not loaded (depth/size cap); fetch on expand.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// Files only; `None` if the file could not be read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<FileMeta>,
+    /// Dirs only: set when the dir is the work tree of a nested repo or submodule.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nested_repo: Option<RepoKind>,
+    /// Set by `git_status::annotate`; dirs carry the most significant child status.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitStatus>,
+    /// Display title from book navigation; `name` stays the on-disk name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Grouping node from book navigation with no directory on disk.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub section: bool,
 }
 
-/// Detect git repo root by running `git rev-parse --show-toplevel`.
+/// Detect git repo root (work tree top) without spawning git.
 pub fn detect_git_root(path: &Path) -> Option<PathBuf> {
-    let dir = if path.is_file() {
-        path.parent()?
-    } else {
-        path
-    };
-
-    let output = Command::new("git")
-        .arg("rev-parse")
-        .arg("--show-toplevel")
-        .current_dir(dir)
-        .output()
-        .ok()?;
+    git_repo::discover(path).map(|repo| repo.work_tree)
+}
 
-    if output.status.success() {
-        let root = String::from_utf8(output.stdout).ok()?.trim().to_string();
-        Some(PathBuf::from(root))
-    } else {
-        None
+/// Work trees of nested repos and submodules marked in `nodes`, outermost first.
+pub fn nested_repo_roots(nodes: &[FileNode]) -> Vec<PathBuf> {
+    let mut out = Vec::new();
+    for node in nodes {
+        if node.nested_repo.is_some() {
+            out.push(PathBuf::from(&node.path));
+        }
+        if let Some(children) = &node.children {
+            out.extend(nested_repo_roots(children));
+        }
     }
+    out
 }
 
 /// Resolve context root: provided git root or parent dir of the file.
@@ -47,112 +79,537 @@ pub fn resolve_root(path: &Path, git_root: Option<PathBuf>) -> PathBuf {
     })
 }
 
-/// Scan `root` for .md files, build a pruned tree (no empty dirs).
-pub fn build_md_tree(root: &Path) -> Vec<FileNode> {
-    let mut md_files: Vec<PathBuf> = Vec::new();
+/// Which part of a root a scan covers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanScope {
+    /// The whole root.
+    All,
+    /// Everything below a directory.
+    Dir(PathBuf),
+    /// Only the files directly inside a directory.
+    DirShallow(PathBuf),
+}
+
+impl ScanScope {
+    fn allows_dir(&self, dir: &Path) -> bool {
+        match self {
+            ScanScope::All => true,
+            // ancestors must be walked to apply their ignore files
+            ScanScope::Dir(scope) => scope.starts_with(dir) || dir.starts_with(scope),
+            ScanScope::DirShallow(scope) => scope.starts_with(dir),
+        }
+    }
 
-    for entry in WalkDir::new(root)
+    fn allows_file(&self, file: &Path) -> bool {
+        match self {
+            ScanScope::All => true,
+            ScanScope::Dir(scope) => file.starts_with(scope),
+            ScanScope::DirShallow(scope) => file.parent() == Some(scope.as_path()),
+        }
+    }
+}
+
+/// Walker over `root` with ignore files, config rules and `scope` applied.
+///
+/// Honors `.gitignore`, `.git/info/exclude` and the global git excludes file
+/// (only inside a git repo, like git itself), plus `.mdcatignore` anywhere.
+/// Scoped walks still start at `root` so that ignore files in ancestor dirs apply.
+fn walker(root: &Path, config: &TreeConfig, scope: &ScanScope) -> WalkBuilder {
+    let filter_root = root.to_path_buf();
+    let filter_config = config.clone();
+    let filter_matcher = config.matcher();
+    let filter_scope = scope.clone();
+
+    let mut builder = WalkBuilder::new(root);
+    builder
         .follow_links(true)
-        .into_iter()
-        .filter_entry(|e| {
-            let name = e.file_name().to_string_lossy();
-            // skip hidden dirs and node_modules/target
-            if e.file_type().is_dir() {
-                return !name.starts_with('.')
-                    && name != "node_modules"
-                    && name != "target";
+        // hidden files are fine; hidden dirs are filtered below
+        .hidden(false)
+        .git_ignore(true)
+        .git_exclude(true)
+        .git_global(true)
+        .add_custom_ignore_filename(MDCAT_IGNORE_FILENAME)
+        .filter_entry(move |e| {
+            if e.file_type().is_some_and(|t| t.is_dir()) {
+                let name = e.file_name().to_string_lossy();
+                if !filter_config.allows_dir_name(&name) {
+                    return false;
+                }
+                if !filter_scope.allows_dir(e.path()) {
+                    return false;
+                }
+                let rel = e.path().strip_prefix(&filter_root).unwrap_or(e.path());
+                return !filter_matcher.is_excluded(rel);
             }
             true
-        })
+        });
+    builder
+}
+
+/// Whether a walked file is a markdown file the config lets through.
+fn accepts_file(root: &Path, config: &TreeConfig, matcher: &PathMatcher, path: &Path) -> bool {
+    if !config.has_markdown_ext(path) {
+        return false;
+    }
+    let rel = path.strip_prefix(root).unwrap_or(path);
+    matcher.is_included(rel) && !matcher.is_excluded(rel)
+}
+
+/// Collect markdown files under `root` that fall inside `scope`.
+/// Extensions, skipped dirs and globs come from `config`.
+pub fn scan_md_files(root: &Path, config: &TreeConfig, scope: ScanScope) -> Vec<PathBuf> {
+    let matcher = config.matcher();
+    walker(root, config, &scope)
+        .build()
         .flatten()
-    {
-        if entry.file_type().is_file() {
+        .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+        .map(|e| e.into_path())
+        .filter(|p| scope.allows_file(p) && accepts_file(root, config, &matcher, p))
+        .collect()
+}
+
+/// Collect asset files (see `ASSET_EXTENSIONS`) under `root`, with the same
+/// ignore files, skipped dirs and exclude globs as markdown files.
+pub fn scan_asset_files(root: &Path, config: &TreeConfig) -> Vec<PathBuf> {
+    scan_files_by_ext(root, config, ASSET_EXTENSIONS)
+}
+
+/// Collect files with one of `extensions` (case-insensitive) under `root`,
+/// filtered like `scan_asset_files`.
+pub fn scan_files_by_ext(root: &Path, config: &TreeConfig, extensions: &[&str]) -> Vec<PathBuf> {
+    let matcher = config.matcher();
+    walker(root, config, &ScanScope::All)
+        .build()
+        .flatten()
+        .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+        .map(|e| e.into_path())
+        .filter(|p| {
+            let matches = p.extension().is_some_and(|ext| {
+                let ext = ext.to_string_lossy();
+                extensions.iter().any(|a| a.eq_ignore_ascii_case(&ext))
+            });
+            matches && !matcher.is_excluded(p.strip_prefix(root).unwrap_or(p))
+        })
+        .collect()
+}
+
+/// Parallel variant of `scan_md_files`: `visit` is called (from walker
+/// threads, in no particular order) for each accepted file; returning false
+/// stops the whole walk, e.g. on cancellation. `stamp` receives the mtime of
+/// every walked directory and rule file, which is what decides whether a
+/// saved result is still current (see `tree_cache`).
+pub fn scan_md_files_parallel<F, S>(
+    root: &Path,
+    config: &TreeConfig,
+    scope: ScanScope,
+    visit: F,
+    stamp: S,
+) where
+    F: Fn(&Path) -> bool + Sync,
+    S: Fn(&Path, u64) + Sync,
+{
+    let matcher = config.matcher();
+    let (scope, matcher, visit, stamp) = (&scope, &matcher, &visit, &stamp);
+    walker(root, config, scope).build_parallel().run(|| {
+        Box::new(move |entry| {
+            let Ok(entry) = entry else {
+                return WalkState::Continue;
+            };
             let path = entry.path();
-            if let Some(ext) = path.extension() {
-                if ext == "md" || ext == "MD" || ext == "markdown" {
-                    md_files.push(path.to_path_buf());
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            if is_dir || is_rule_file(path) {
+                if let Some(mtime) = mtime_ms(path) {
+                    stamp(path, mtime);
                 }
             }
+            if !entry.file_type().is_some_and(|t| t.is_file())
+                || !scope.allows_file(path)
+                || !accepts_file(root, config, matcher, path)
+            {
+                return WalkState::Continue;
+            }
+            if visit(path) {
+                WalkState::Continue
+            } else {
+                WalkState::Quit
+            }
+        })
+    });
+}
+
+/// Files whose change can alter which markdown files are visible anywhere.
+pub fn is_rule_file(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default();
+    name == ".gitignore" || name == MDCAT_IGNORE_FILENAME || name == REPO_CONFIG_FILENAME
+}
+
+/// Join without touching the filesystem, folding `.` and `..`.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    out.push("..");
+                }
+            }
+            c => out.push(c),
         }
     }
+    out
+}
 
-    assemble_tree(root, &md_files)
+/// Modification time in milliseconds since the epoch.
+pub fn mtime_ms(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64)
 }
 
-/// Build a nested tree structure from flat list of .md file paths.
-fn assemble_tree(root: &Path, files: &[PathBuf]) -> Vec<FileNode> {
-    // dir_path -> list of children nodes
-    let mut dir_children: HashMap<PathBuf, Vec<FileNode>> = HashMap::new();
+/// Direct children of `dir` without walking below it: markdown files plus
+/// every visible subdir, marked `truncated` since emptiness isn't known yet.
+/// Used before the full index is ready.
+pub fn list_children(root: &Path, config: &TreeConfig, dir: &Path) -> Vec<FileNode> {
+    let matcher = config.matcher();
+    let depth = dir
+        .strip_prefix(root)
+        .map(|r| r.components().count())
+        .unwrap_or(0);
+    let mut result = Vec::new();
 
-    // collect all dirs that need to exist
-    for file in files {
-        let rel = file.strip_prefix(root).unwrap_or(file.as_path());
-        let file_name = rel
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
-
-        let node = FileNode {
-            name: file_name,
-            path: file.to_string_lossy().to_string(),
+    for entry in walker(root, config, &ScanScope::Dir(dir.to_path_buf()))
+        .max_depth(Some(depth + 1))
+        .build()
+        .flatten()
+    {
+        let path = entry.path();
+        if entry.depth() != depth + 1 || path.parent() != Some(dir) {
+            continue;
+        }
+        match entry.file_type() {
+            Some(t) if t.is_dir() => {
+                let mut node = FileNode::dir(root, path, Vec::new());
+                node.children = None;
+                node.truncated = true;
+                result.push(node);
+            }
+            Some(t) if t.is_file() && accepts_file(root, config, &matcher, path) => {
+                result.push(FileNode::file(root, path));
+            }
+            _ => {}
+        }
+    }
+
+    sort_nodes(&mut result, config.tree_sort());
+    result
+}
+
+fn is_hidden_rel(rel: &Path) -> bool {
+    rel.components()
+        .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+}
+
+fn file_name_of(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+impl FileNode {
+    fn file(root: &Path, path: &Path) -> FileNode {
+        FileNode {
+            name: file_name_of(path),
+            path: path.to_string_lossy().to_string(),
             is_dir: false,
+            is_hidden: is_hidden_rel(path.strip_prefix(root).unwrap_or(path)),
             children: None,
+            truncated: false,
+            meta: file_meta::get(path),
+            nested_repo: None,
+            git: None,
+            label: None,
+            section: false,
+        }
+    }
+
+    fn dir(root: &Path, path: &Path, children: Vec<FileNode>) -> FileNode {
+        FileNode {
+            name: file_name_of(path),
+            path: path.to_string_lossy().to_string(),
+            is_dir: true,
+            is_hidden: is_hidden_rel(path.strip_prefix(root).unwrap_or(path)),
+            children: Some(children),
+            truncated: false,
+            meta: None,
+            nested_repo: git_repo::repo_at(path).map(|r| r.kind),
+            git: None,
+            label: None,
+            section: false,
+        }
+    }
+
+    /// Book navigation group; `path` is a synthetic id unique within `root`.
+    fn section(root: &Path, id: &str, title: &str, children: Vec<FileNode>) -> FileNode {
+        FileNode {
+            name: title.to_string(),
+            path: format!("{}/#nav/{}", root.to_string_lossy(), id),
+            is_dir: true,
+            is_hidden: false,
+            children: Some(children),
+            truncated: false,
+            meta: None,
+            nested_repo: None,
+            git: None,
+            label: Some(title.to_string()),
+            section: true,
+        }
+    }
+}
+
+fn sort_nodes(nodes: &mut [FileNode], sort: TreeSort) {
+    nodes.sort_by_cached_key(|node| {
+        let group = match sort.order {
+            SortOrder::DirsFirst => !node.is_dir,
+            SortOrder::FilesFirst => node.is_dir,
+            SortOrder::Mixed => false,
+        };
+        let newest = match sort.by {
+            SortKey::Modified => std::cmp::Reverse(newest_mtime(node)),
+            _ => std::cmp::Reverse(0),
         };
+        let text = match sort.by {
+            SortKey::Name => vec![NameChunk::Text(node.name.to_lowercase())],
+            SortKey::Title => natural_key(
+                node.meta
+                    .as_ref()
+                    .and_then(|m| m.title.as_deref())
+                    .unwrap_or(&node.name),
+            ),
+            SortKey::Natural | SortKey::Modified | SortKey::Book => natural_key(&node.name),
+        };
+        // raw name last so case-only differences still order deterministically
+        (group, newest, text, node.name.clone())
+    });
+}
+
+/// One run of a natural sort key. Digit runs compare by value: shorter
+/// (after stripping leading zeros) is smaller, then digit by digit.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum NameChunk {
+    Number(usize, String),
+    Text(String),
+}
+
+fn natural_key(name: &str) -> Vec<NameChunk> {
+    let mut chunks = Vec::new();
+    let mut rest = name;
+    while let Some(c) = rest.chars().next() {
+        let is_digit = c.is_ascii_digit();
+        let end = rest
+            .find(|ch: char| ch.is_ascii_digit() != is_digit)
+            .unwrap_or(rest.len());
+        let (run, tail) = rest.split_at(end);
+        chunks.push(if is_digit {
+            let digits = run.trim_start_matches('0').to_string();
+            NameChunk::Number(digits.len(), digits)
+        } else {
+            NameChunk::Text(run.to_lowercase())
+        });
+        rest = tail;
+    }
+    chunks
+}
+
+/// A file's mtime; for a directory, its newest loaded descendant (or its
+/// own mtime when children weren't loaded).
+fn newest_mtime(node: &FileNode) -> u64 {
+    match &node.children {
+        Some(children) => children.iter().map(newest_mtime).max().unwrap_or(0),
+        None if node.is_dir => mtime_ms(Path::new(&node.path)).unwrap_or(0),
+        None => node.meta.as_ref().map(|m| m.mtime_ms).unwrap_or(0),
+    }
+}
 
-        let parent = rel.parent().unwrap_or(Path::new(""));
-        dir_children
-            .entry(root.join(parent))
+/// Build a nested tree structure from flat list of .md file paths.
+pub fn assemble_tree(root: &Path, files: &[PathBuf], sort: TreeSort) -> Vec<FileNode> {
+    // dir_path -> file nodes directly inside it
+    let mut dir_files: HashMap<PathBuf, Vec<FileNode>> = HashMap::new();
+    // dir_path -> immediate subdirs that (transitively) contain files
+    let mut subdirs: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+
+    for file in files {
+        let parent = file.parent().unwrap_or(root).to_path_buf();
+        dir_files
+            .entry(parent.clone())
             .or_default()
-            .push(node);
+            .push(FileNode::file(root, file));
 
-        // ensure all ancestor dirs exist in the map
-        let mut ancestor = parent;
-        while ancestor != Path::new("") {
-            dir_children.entry(root.join(ancestor)).or_default();
-            ancestor = ancestor.parent().unwrap_or(Path::new(""));
+        // link each ancestor into its parent once, stopping at the first known one
+        let mut dir = parent;
+        while dir != root && dir.starts_with(root) {
+            let Some(up) = dir.parent().map(Path::to_path_buf) else {
+                break;
+            };
+            let siblings = subdirs.entry(up.clone()).or_default();
+            if siblings.contains(&dir) {
+                break;
+            }
+            siblings.push(dir);
+            dir = up;
         }
     }
 
-    fn build_level(dir: &Path, dir_children: &HashMap<PathBuf, Vec<FileNode>>) -> Vec<FileNode> {
-        let mut result: Vec<FileNode> = Vec::new();
+    fn build_level(
+        root: &Path,
+        dir: &Path,
+        dir_files: &mut HashMap<PathBuf, Vec<FileNode>>,
+        subdirs: &HashMap<PathBuf, Vec<PathBuf>>,
+        sort: TreeSort,
+    ) -> Vec<FileNode> {
+        let mut result = dir_files.remove(dir).unwrap_or_default();
 
-        // add files in this dir
-        if let Some(nodes) = dir_children.get(dir) {
-            for node in nodes {
-                if !node.is_dir {
-                    result.push(node.clone());
-                }
+        for sub in subdirs.get(dir).into_iter().flatten() {
+            let children = build_level(root, sub, dir_files, subdirs, sort);
+            if !children.is_empty() {
+                result.push(FileNode::dir(root, sub, children));
             }
         }
 
-        // add subdirs that have content
-        for path in dir_children.keys() {
-            if path.parent() == Some(dir) && path != dir {
-                let children = build_level(path, dir_children);
-                if !children.is_empty() {
-                    let name = path
-                        .file_name()
-                        .unwrap_or_default()
-                        .to_string_lossy()
-                        .to_string();
-                    result.push(FileNode {
-                        name,
-                        path: path.to_string_lossy().to_string(),
-                        is_dir: true,
-                        children: Some(children),
-                    });
-                }
+        sort_nodes(&mut result, sort);
+        result
+    }
+
+    build_level(root, root, &mut dir_files, &subdirs, sort)
+}
+
+/// Cap a tree for display: levels below `max_depth` (relative to `nodes`) and
+/// anything past `max_nodes` in breadth-first order become `truncated` dirs
+/// without children, to be fetched with `get_tree_children` on expand.
+pub fn limit_tree(nodes: &mut [FileNode], max_depth: Option<usize>, max_nodes: usize) {
+    let mut budget = max_nodes.saturating_sub(nodes.len());
+    let mut level: Vec<&mut FileNode> = nodes.iter_mut().collect();
+    let mut depth = 1;
+
+    while !level.is_empty() {
+        let mut next: Vec<&mut FileNode> = Vec::new();
+        for node in level {
+            let Some(count) = node.children.as_ref().map(Vec::len) else {
+                continue;
+            };
+            let too_deep = max_depth.is_some_and(|d| depth >= d);
+            if too_deep || count > budget {
+                node.children = None;
+                node.truncated = true;
+                continue;
             }
+            budget -= count;
+            next.extend(node.children.iter_mut().flatten());
         }
+        level = next;
+        depth += 1;
+    }
+}
 
-        // sort: dirs first, then alphabetical
-        result.sort_by(|a, b| {
-            b.is_dir.cmp(&a.is_dir).then(a.name.to_lowercase().cmp(&b.name.to_lowercase()))
-        });
+/// Subtree of `dir` (a directory inside `root`) assembled from `files`.
+pub fn subtree(root: &Path, dir: &Path, files: &[PathBuf], sort: TreeSort) -> Vec<FileNode> {
+    let inside: Vec<PathBuf> = files
+        .iter()
+        .filter(|f| f.starts_with(dir))
+        .cloned()
+        .collect();
+    let mut nodes = assemble_tree(root, &inside, sort);
+    if dir == root {
+        return nodes;
+    }
+    let Ok(rel) = dir.strip_prefix(root) else {
+        return Vec::new();
+    };
+    // descend the (single-branch) ancestor chain down to `dir`
+    for comp in rel.components() {
+        let name = comp.as_os_str().to_string_lossy();
+        match nodes.into_iter().find(|n| n.is_dir && n.name == name) {
+            Some(node) => nodes = node.children.unwrap_or_default(),
+            None => return Vec::new(),
+        }
+    }
+    nodes
+}
+
+/// Children of the dir or book section at `path` anywhere in `nodes`.
+pub fn find_children(nodes: Vec<FileNode>, path: &str) -> Option<Vec<FileNode>> {
+    for node in nodes {
+        if node.path == path {
+            return node.children;
+        }
+        if let Some(found) = node.children.and_then(|c| find_children(c, path)) {
+            return Some(found);
+        }
+    }
+    None
+}
 
+/// "Book order" tree: `items` (from `book_nav`) in declared order with
+/// declared titles, limited to scanned `files`. A page with sub-pages
+/// becomes a section holding the page followed by its children; scanned
+/// files the navigation doesn't mention go into a trailing "Other" section,
+/// laid out by directory as usual.
+pub fn assemble_book_tree(
+    root: &Path,
+    items: &[NavItem],
+    files: &[PathBuf],
+    sort: TreeSort,
+) -> Vec<FileNode> {
+    fn build(
+        root: &Path,
+        items: &[NavItem],
+        available: &HashSet<&Path>,
+        declared: &mut HashSet<PathBuf>,
+        next_id: &mut usize,
+    ) -> Vec<FileNode> {
+        let mut result = Vec::new();
+        for item in items {
+            let page = item
+                .path
+                .as_deref()
+                .filter(|p| available.contains(p) && !declared.contains(*p))
+                .map(|p| {
+                    declared.insert(p.to_path_buf());
+                    let mut node = FileNode::file(root, p);
+                    node.label = item.title.clone();
+                    node
+                });
+            let children = build(root, &item.children, available, declared, next_id);
+            if children.is_empty() {
+                // drafts, external links and missing pages drop out
+                result.extend(page);
+                continue;
+            }
+            let title = item
+                .title
+                .clone()
+                .or_else(|| page.as_ref().map(|p| p.name.clone()))
+                .unwrap_or_default();
+            *next_id += 1;
+            let nodes = page.into_iter().chain(children).collect();
+            result.push(FileNode::section(root, &next_id.to_string(), &title, nodes));
+        }
         result
     }
 
-    build_level(root, &dir_children)
+    let available: HashSet<&Path> = files.iter().map(PathBuf::as_path).collect();
+    let mut declared = HashSet::new();
+    let mut next_id = 0;
+    let mut result = build(root, items, &available, &mut declared, &mut next_id);
+
+    let other: Vec<PathBuf> = files
+        .iter()
+        .filter(|f| !declared.contains(*f))
+        .cloned()
+        .collect();
+    if !other.is_empty() {
+        let children = assemble_tree(root, &other, sort);
+        result.push(FileNode::section(root, "other", "Other", children));
+    }
+    result
 }
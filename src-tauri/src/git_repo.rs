@@ -0,0 +1,176 @@
+//! In-process git repository discovery: walks up looking for `.git`
+//! directories or `gitdir:` files, like `git rev-parse --show-toplevel` but
+//! without spawning git (which is often missing from PATH for GUI apps).
+//! Repositories found are cached per start directory until a watched root
+//! sees a `.git` entry come or go; misses aren't cached, so a `git init`
+//! anywhere shows up on the next lookup.
+
+use crate::file_tree;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+static CACHE: Mutex<Option<HashMap<PathBuf, GitRepo>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RepoKind {
+    /// Regular checkout with a `.git` directory.
+    Main,
+    /// Linked worktree (`git worktree add`), possibly of a bare repository.
+    Worktree,
+    /// Submodule checkout whose git dir lives in the superproject's `.git/modules`.
+    Submodule,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GitRepo {
+    /// Top of the working tree (what `--show-toplevel` prints).
+    pub work_tree: PathBuf,
+    /// This checkout's git dir (`.git`, or the target of a `gitdir:` file).
+    pub git_dir: PathBuf,
+    /// Shared git dir holding objects and refs; differs from `git_dir` for worktrees.
+    pub common_dir: PathBuf,
+    pub kind: RepoKind,
+}
+
+/// Find the repository containing `path` (a file or directory).
+pub fn discover(path: &Path) -> Option<GitRepo> {
+    let dir = if path.is_file() { path.parent()? } else { path };
+    // git resolves symlinks in the cwd before searching; match its output
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+
+    if let Ok(lock) = CACHE.lock() {
+        if let Some(hit) = lock.as_ref().and_then(|c| c.get(&dir)) {
+            return Some(hit.clone());
+        }
+    }
+
+    let found = discover_uncached(&dir, &ceiling_dirs())?;
+
+    if let Ok(mut lock) = CACHE.lock() {
+        lock.get_or_insert_with(HashMap::new)
+            .insert(dir, found.clone());
+    }
+    Some(found)
+}
+
+/// Forget every cached repository, after a repo, worktree or submodule was
+/// created or removed.
+pub fn invalidate() {
+    if let Ok(mut lock) = CACHE.lock() {
+        *lock = None;
+    }
+}
+
+/// Every repository enclosing `path`, innermost first: a file in a submodule
+/// yields the submodule, then its superproject, and so on outwards.
+pub fn discover_chain(path: &Path) -> Vec<GitRepo> {
+    let mut chain = Vec::new();
+    let mut next = discover(path);
+    while let Some(repo) = next {
+        next = repo.work_tree.parent().and_then(discover);
+        chain.push(repo);
+    }
+    chain
+}
+
+/// Parse `GIT_CEILING_DIRECTORIES`: discovery never moves up into these.
+fn ceiling_dirs() -> Vec<PathBuf> {
+    let Some(raw) = std::env::var_os("GIT_CEILING_DIRECTORIES") else {
+        return Vec::new();
+    };
+    std::env::split_paths(&raw)
+        .filter(|p| p.is_absolute())
+        .map(|p| p.canonicalize().unwrap_or(p))
+        .collect()
+}
+
+fn discover_uncached(start: &Path, ceilings: &[PathBuf]) -> Option<GitRepo> {
+    let mut dir = start;
+    loop {
+        // inside a git dir there is no work tree, same as git
+        if dir.file_name().is_some_and(|n| n == ".git") {
+            return None;
+        }
+        if let Some(repo) = repo_at(dir) {
+            return Some(repo);
+        }
+        let parent = dir.parent()?;
+        if ceilings.iter().any(|c| c == parent) {
+            return None;
+        }
+        dir = parent;
+    }
+}
+
+/// Repository whose work tree is exactly `dir`, if `dir/.git` is valid.
+pub fn repo_at(dir: &Path) -> Option<GitRepo> {
+    let dot_git = dir.join(".git");
+    let meta = std::fs::metadata(&dot_git).ok()?;
+
+    if meta.is_dir() {
+        if !is_git_dir(&dot_git) {
+            return None;
+        }
+        return Some(GitRepo {
+            work_tree: dir.to_path_buf(),
+            common_dir: common_dir_of(&dot_git),
+            git_dir: dot_git,
+            kind: RepoKind::Main,
+        });
+    }
+
+    // gitfile: "gitdir: <path>", relative to the directory holding it
+    let contents = std::fs::read_to_string(&dot_git).ok()?;
+    let target = contents.lines().next()?.strip_prefix("gitdir:")?.trim();
+    let git_dir = resolve(&dir.join(target));
+    if !is_git_dir(&git_dir) {
+        return None;
+    }
+
+    let common_dir = common_dir_of(&git_dir);
+    let kind = if common_dir != git_dir {
+        RepoKind::Worktree
+    } else if is_module_dir(&git_dir) {
+        RepoKind::Submodule
+    } else {
+        // e.g. `git init --separate-git-dir`
+        RepoKind::Main
+    };
+
+    Some(GitRepo {
+        work_tree: dir.to_path_buf(),
+        git_dir,
+        common_dir,
+        kind,
+    })
+}
+
+/// A git dir has `HEAD` plus either `objects/` or a `commondir` pointer.
+fn is_git_dir(path: &Path) -> bool {
+    path.join("HEAD").is_file()
+        && (path.join("objects").is_dir() || path.join("commondir").is_file())
+}
+
+fn common_dir_of(git_dir: &Path) -> PathBuf {
+    match std::fs::read_to_string(git_dir.join("commondir")) {
+        Ok(contents) => resolve(&git_dir.join(contents.trim())),
+        Err(_) => git_dir.to_path_buf(),
+    }
+}
+
+/// Submodule git dirs live at `<super>/.git/modules/<name>`.
+fn is_module_dir(git_dir: &Path) -> bool {
+    let names: Vec<_> = git_dir.components().map(|c| c.as_os_str()).collect();
+    names
+        .windows(2)
+        .any(|w| w[0] == ".git" && w[1] == "modules")
+}
+
+/// Canonicalize if possible, otherwise fold `.`/`..` lexically.
+fn resolve(path: &Path) -> PathBuf {
+    path.canonicalize()
+        .unwrap_or_else(|_| file_tree::normalize(path))
+}
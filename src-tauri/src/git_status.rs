@@ -0,0 +1,138 @@
+//! Git status decorations for the tree, from `git status --porcelain=v2 -z`.
+//! Directory nodes take the most significant status among their children.
+
+use crate::config::{TreeConfig, CURSOR_DIR, CURSOR_RULES_EXT};
+use crate::file_tree::FileNode;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Ordered by significance; a directory shows the max of its children.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitStatus {
+    Untracked,
+    Staged,
+    Modified,
+    Conflicted,
+}
+
+/// Status of every changed markdown file under `root`, keyed by absolute path.
+/// `git_root` is the repository toplevel that porcelain paths are relative to.
+pub fn collect(
+    root: &Path,
+    git_root: &Path,
+    config: &TreeConfig,
+) -> Result<HashMap<PathBuf, GitStatus>, String> {
+    let mut pathspecs: Vec<String> = config
+        .extensions
+        .iter()
+        .map(|ext| format!(":(glob,icase)**/*.{}", ext))
+        .collect();
+    if config.shows_cursor_rules() {
+        pathspecs.push(format!(
+            ":(glob,icase)**/{}/**/*.{}",
+            CURSOR_DIR, CURSOR_RULES_EXT
+        ));
+    }
+
+    let output = Command::new("git")
+        .args([
+            "status",
+            "--porcelain=v2",
+            "-z",
+            "--untracked-files=all",
+            "--",
+        ])
+        .args(&pathspecs)
+        .current_dir(root)
+        .output()
+        .map_err(|e| format!("Failed to run git status: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "git status failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    // Porcelain paths hang off the canonical git root; key them under `root`
+    // as opened so symlinked roots still match the tree's paths.
+    let canonical_root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    Ok(parse_porcelain_v2(&output.stdout, git_root)
+        .into_iter()
+        .map(|(path, status)| match path.strip_prefix(&canonical_root) {
+            Ok(rel) => (root.join(rel), status),
+            Err(_) => (path, status),
+        })
+        .collect())
+}
+
+fn parse_porcelain_v2(out: &[u8], git_root: &Path) -> HashMap<PathBuf, GitStatus> {
+    let mut result = HashMap::new();
+    let mut records = out
+        .split(|b| *b == 0)
+        .map(|r| String::from_utf8_lossy(r).into_owned());
+
+    while let Some(record) = records.next() {
+        let (status, path) = match record.as_bytes().first() {
+            // 1 XY sub mH mI mW hH hI path
+            Some(b'1') => match record.splitn(9, ' ').collect::<Vec<_>>()[..] {
+                [_, xy, _, _, _, _, _, _, path] => (xy_status(xy), path.to_string()),
+                _ => continue,
+            },
+            // 2 XY sub mH mI mW hH hI score path \0 origPath
+            Some(b'2') => {
+                let _orig = records.next();
+                match record.splitn(10, ' ').collect::<Vec<_>>()[..] {
+                    [_, xy, _, _, _, _, _, _, _, path] => (xy_status(xy), path.to_string()),
+                    _ => continue,
+                }
+            }
+            // u XY sub m1 m2 m3 mW h1 h2 h3 path
+            Some(b'u') => match record.splitn(11, ' ').collect::<Vec<_>>()[..] {
+                [.., path] => (GitStatus::Conflicted, path.to_string()),
+                _ => continue,
+            },
+            Some(b'?') => match record.get(2..) {
+                Some(path) => (GitStatus::Untracked, path.to_string()),
+                None => continue,
+            },
+            _ => continue,
+        };
+        result.insert(git_root.join(path), status);
+    }
+
+    result
+}
+
+/// Porcelain `XY`: X is the index, Y the worktree; `.` means unchanged.
+fn xy_status(xy: &str) -> GitStatus {
+    let mut chars = xy.chars();
+    let x = chars.next().unwrap_or('.');
+    let y = chars.next().unwrap_or('.');
+    if y != '.' {
+        GitStatus::Modified
+    } else if x != '.' {
+        GitStatus::Staged
+    } else {
+        GitStatus::Modified
+    }
+}
+
+/// Set `git` on file nodes and bubble the most significant status up to dirs.
+pub fn annotate(
+    nodes: &mut [FileNode],
+    statuses: &HashMap<PathBuf, GitStatus>,
+) -> Option<GitStatus> {
+    let mut max: Option<GitStatus> = None;
+    for node in nodes.iter_mut() {
+        node.git = match node.children.as_mut() {
+            Some(children) => annotate(children, statuses),
+            None => statuses.get(Path::new(&node.path)).copied(),
+        };
+        max = max.max(node.git);
+    }
+    max
+}
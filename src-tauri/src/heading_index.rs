@@ -0,0 +1,355 @@
+//! Every heading of every markdown file under a root, with GitHub-style
+//! slugs, for "go to heading anywhere" and file outlines.
+//!
+//! A root's headings are parsed the first time they're searched and then
+//! follow the tree watcher's diffs; roots no longer watched are dropped.
+//! Until a root's watcher index is ready its headings are kept with each
+//! file's mtime, and a search re-reads only the files that changed.
+
+use crate::config::TreeConfig;
+use crate::file_meta::{self, FenceState};
+use crate::file_tree::{self, ScanScope};
+use crate::quick_open::{self, Text};
+use crate::tree_watcher::{self, TreeDiff};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Results returned when the caller doesn't ask for a number.
+pub const DEFAULT_LIMIT: usize = 50;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Heading {
+    pub level: u8,
+    /// Heading text with inline markup (links, code, emphasis) stripped.
+    pub text: String,
+    /// Anchor as GitHub generates it, `-1`, `-2`... suffixed when repeated.
+    pub slug: String,
+    /// 1-based source line.
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HeadingMatch {
+    pub path: String,
+    /// Path relative to its root.
+    pub rel_path: String,
+    #[serde(flatten)]
+    pub heading: Heading,
+    pub score: i32,
+    /// Char indices of matched chars in `text`.
+    pub positions: Vec<usize>,
+}
+
+struct IndexedHeading {
+    heading: Heading,
+    text: Text,
+}
+
+/// Headings per file of one root.
+type FileHeadings = HashMap<PathBuf, Vec<IndexedHeading>>;
+
+/// Headings per file with the file's mtime when parsed.
+type StampedHeadings = HashMap<PathBuf, (Option<u64>, Vec<IndexedHeading>)>;
+
+static INDEX: Mutex<Option<HashMap<PathBuf, FileHeadings>>> = Mutex::new(None);
+
+/// Roots searched without a watcher index.
+static UNWATCHED: Mutex<Option<HashMap<PathBuf, StampedHeadings>>> = Mutex::new(None);
+
+/// Diffs that found their root not loaded; headings built while one arrived
+/// may predate it, so they aren't kept.
+static MISSED_DIFFS: AtomicU64 = AtomicU64::new(0);
+
+/// Text as rendered: `[label](url)` -> `label`, images dropped to their alt
+/// text, and code/emphasis markers removed.
+fn plain_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '[' || (c == '!' && rest[1..].starts_with('[')) {
+            let open = rest.find('[').unwrap_or(0);
+            if let Some(close) = rest[open..].find("](").map(|i| open + i) {
+                if let Some(end) = rest[close..].find(')').map(|i| close + i) {
+                    out.push_str(&plain_text(&rest[open + 1..close]));
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+        }
+        if !matches!(c, '`' | '*') {
+            out.push(c);
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    // `_emphasis_` loses its underscores, `snake_case` keeps them
+    let trimmed = out.trim();
+    match trimmed.strip_prefix('_').and_then(|t| t.strip_suffix('_')) {
+        Some(inner) if !inner.contains('_') => inner.to_string(),
+        _ => trimmed.to_string(),
+    }
+}
+
+/// GitHub's anchor for a heading: lowercase, keep letters, digits, `-` and
+/// `_`, turn each space into `-`, drop the rest.
+pub fn slug(text: &str) -> String {
+    text.chars()
+        .flat_map(char::to_lowercase)
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Lines that can't be the text of a setext heading (list items, quotes, tables).
+fn is_block_start(line: &str) -> bool {
+    let t = line.trim_start();
+    line.len() - t.len() >= 4
+        || t.starts_with(['-', '*', '+', '>', '|', '<'])
+        || t.split_once(['.', ')'])
+            .is_some_and(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Headings of a markdown document in order: ATX (`## Title`) and setext
+/// (`Title` over `===` / `---`), skipping fenced code and frontmatter.
+pub fn parse(content: &str) -> Vec<Heading> {
+    let (_, body, body_start) = file_meta::split_frontmatter(content);
+    let mut headings = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut push = |level: u8, raw: &str, line: usize| {
+        let text = plain_text(raw);
+        let base = slug(&text);
+        let count = seen.entry(base.clone()).or_insert(0);
+        let slug = match *count {
+            0 => base,
+            n => format!("{}-{}", base, n),
+        };
+        *count += 1;
+        headings.push(Heading {
+            level,
+            text,
+            slug,
+            line,
+        });
+    };
+
+    let mut fences = FenceState::default();
+    // previous line, if it could still become a setext heading
+    let mut paragraph: Option<(&str, usize)> = None;
+    for (i, line) in body.lines().enumerate() {
+        let line_no = body_start + i + 1;
+        if fences.is_code(line) {
+            paragraph = None;
+            continue;
+        }
+        if let Some((level, text)) = file_meta::atx_heading(line) {
+            push(level, text, line_no);
+            paragraph = None;
+            continue;
+        }
+        let t = line.trim();
+        if let Some((text, text_line)) = paragraph.filter(|_| !t.is_empty()) {
+            let level = if t.chars().all(|c| c == '=') {
+                Some(1)
+            } else if t.chars().all(|c| c == '-') {
+                Some(2)
+            } else {
+                None
+            };
+            if let Some(level) = level {
+                push(level, text.trim(), text_line);
+                paragraph = None;
+                continue;
+            }
+        }
+        paragraph = (!t.is_empty() && !is_block_start(line)).then_some((line, line_no));
+    }
+    headings
+}
+
+/// Headings of the file at `path`, straight from disk.
+pub fn outline(path: &Path) -> Result<Vec<Heading>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(parse(&content))
+}
+
+fn index_file(path: &Path) -> Vec<IndexedHeading> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    parse(&content)
+        .into_iter()
+        .map(|heading| IndexedHeading {
+            text: Text::new(&heading.text),
+            heading,
+        })
+        .collect()
+}
+
+/// Apply a `tree-changed` diff to the root's headings, if they're loaded.
+pub fn apply_diff(diff: &TreeDiff) {
+    let Ok(mut lock) = INDEX.lock() else {
+        return;
+    };
+    let Some(files) = lock.as_mut().and_then(|i| i.get_mut(Path::new(&diff.root))) else {
+        MISSED_DIFFS.fetch_add(1, Ordering::SeqCst);
+        return;
+    };
+    for path in &diff.removed {
+        files.remove(Path::new(path));
+    }
+    for renamed in &diff.renamed {
+        files.remove(Path::new(&renamed.from));
+    }
+    let changed = diff.added.iter().chain(diff.renamed.iter().map(|r| &r.to));
+    for path in changed {
+        files.insert(PathBuf::from(path), index_file(Path::new(path)));
+    }
+    for path in &diff.modified {
+        // `modified` also carries non-markdown files such as `mkdocs.yml`
+        if let Some(headings) = files.get_mut(Path::new(path)) {
+            *headings = index_file(Path::new(path));
+        }
+    }
+}
+
+/// Drop loaded headings of roots not in `keep`.
+pub fn retain(keep: &[PathBuf]) {
+    if let Ok(mut lock) = INDEX.lock() {
+        if let Some(index) = lock.as_mut() {
+            index.retain(|root, _| keep.contains(root));
+        }
+    }
+    if let Ok(mut lock) = UNWATCHED.lock() {
+        if let Some(unwatched) = lock.as_mut() {
+            unwatched.retain(|root, _| keep.contains(root));
+        }
+    }
+}
+
+/// Headings of an unwatched root, re-reading only files whose mtime
+/// differs from the last search's.
+fn refresh_unwatched(root: &Path, config: &TreeConfig) -> StampedHeadings {
+    let mut old = UNWATCHED
+        .lock()
+        .ok()
+        .and_then(|mut l| l.as_mut()?.remove(root))
+        .unwrap_or_default();
+    file_tree::scan_md_files(root, config, ScanScope::All)
+        .into_iter()
+        .map(|path| {
+            let mtime = file_tree::mtime_ms(&path);
+            let headings = match old.remove(&path) {
+                Some((seen, headings)) if mtime.is_some() && seen == mtime => headings,
+                _ => index_file(&path),
+            };
+            (path, (mtime, headings))
+        })
+        .collect()
+}
+
+/// Up to `limit` headings under `roots` fuzzy-matching `query`, best first.
+/// Roots without a ready watcher index are refreshed by mtime instead of
+/// followed; parsing happens without the lock held.
+pub fn search(roots: &[(PathBuf, TreeConfig)], query: &str, limit: usize) -> Vec<HeadingMatch> {
+    let terms = quick_open::query_terms(query);
+    if terms.is_empty() {
+        return Vec::new();
+    }
+    let missed = MISSED_DIFFS.load(Ordering::SeqCst);
+    let loaded: Vec<PathBuf> = match INDEX.lock() {
+        Ok(lock) => lock.iter().flat_map(|i| i.keys().cloned()).collect(),
+        Err(_) => return Vec::new(),
+    };
+
+    let build = |files: Vec<PathBuf>| -> FileHeadings {
+        files
+            .into_iter()
+            .map(|p| {
+                let headings = index_file(&p);
+                (p, headings)
+            })
+            .collect()
+    };
+    let mut built: Vec<(PathBuf, FileHeadings)> = Vec::new();
+    let mut unwatched: HashMap<PathBuf, StampedHeadings> = HashMap::new();
+    for (root, config) in roots.iter().filter(|(root, _)| !loaded.contains(root)) {
+        match tree_watcher::cached_files(root, root) {
+            Some(files) => {
+                // the watcher's index takes over from the mtime cache
+                if let Some(unwatched) = UNWATCHED.lock().ok().as_mut().and_then(|l| l.as_mut()) {
+                    unwatched.remove(root);
+                }
+                built.push((root.clone(), build(files)));
+            }
+            None => {
+                unwatched.insert(root.clone(), refresh_unwatched(root, config));
+            }
+        }
+    }
+
+    let Ok(mut lock) = INDEX.lock() else {
+        return Vec::new();
+    };
+    let index = lock.get_or_insert_with(HashMap::new);
+    let fresh = MISSED_DIFFS.load(Ordering::SeqCst) == missed;
+    let mut uncached = HashMap::new();
+    for (root, files) in built {
+        if fresh {
+            index.entry(root).or_insert(files);
+        } else {
+            uncached.insert(root, files);
+        }
+    }
+
+    let mut matches = Vec::new();
+    for (root, _) in roots {
+        let files: Vec<(&PathBuf, &Vec<IndexedHeading>)> =
+            match index.get(root).or_else(|| uncached.get(root)) {
+                Some(files) => files.iter().collect(),
+                None => match unwatched.get(root) {
+                    Some(files) => files.iter().map(|(p, (_, h))| (p, h)).collect(),
+                    None => continue,
+                },
+            };
+
+        for (path, headings) in files {
+            for h in headings {
+                let Some((score, positions)) = quick_open::match_terms(&terms, &h.text) else {
+                    continue;
+                };
+                matches.push(HeadingMatch {
+                    path: path.to_string_lossy().to_string(),
+                    rel_path: path
+                        .strip_prefix(root)
+                        .unwrap_or(path)
+                        .to_string_lossy()
+                        .to_string(),
+                    heading: h.heading.clone(),
+                    // a shallower heading wins a tie
+                    score: score - h.heading.level as i32,
+                    positions,
+                });
+            }
+        }
+    }
+
+    drop(lock);
+    if let Ok(mut lock) = UNWATCHED.lock() {
+        lock.get_or_insert_with(HashMap::new).extend(unwatched);
+    }
+
+    matches.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.rel_path.cmp(&b.rel_path))
+            .then(a.heading.line.cmp(&b.heading.line))
+    });
+    matches.truncate(limit);
+    matches
+}
@@ -1,9 +1,28 @@
+mod asset_report;
+mod book_nav;
 mod commands;
+mod config;
+mod file_meta;
 mod file_tree;
 mod file_watcher;
+mod git_repo;
+mod git_status;
+mod heading_index;
 mod instance_registry;
+mod link_check;
+mod link_complete;
+mod link_graph;
+mod link_rewrite;
+mod quick_open;
+mod search_index;
+mod tree_cache;
+mod tree_scan;
+mod tree_watcher;
+mod workspace;
+mod workspace_replace;
+mod workspace_search;
 
-use commands::{CurrentRoot, OpenedFile};
+use commands::{CurrentRoot, CurrentWorkspace, OpenedFile};
 use std::sync::Mutex;
 use tauri::menu::{MenuBuilder, MenuItem, SubmenuBuilder};
 use tauri::{Emitter, Manager};
@@ -30,9 +49,15 @@ fn format_build_date_local(build_date_utc: &str) -> String {
         .unwrap_or_else(|_| build_date_utc.to_string())
 }
 
+/// Headless `mdcat check-links`; returns the process exit code.
+pub fn check_links_cli(args: &[String]) -> i32 {
+    link_check::run_cli(args)
+}
+
 pub fn run() {
     let opened_file: OpenedFile = OpenedFile(Mutex::new(None));
     let current_root: CurrentRoot = CurrentRoot(Mutex::new(None));
+    let current_workspace: CurrentWorkspace = CurrentWorkspace(Mutex::new(None));
 
     tauri::Builder::default()
         .plugin(tauri_plugin_fs::init())
@@ -41,19 +66,47 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .manage(opened_file)
         .manage(current_root)
+        .manage(current_workspace)
         .invoke_handler(tauri::generate_handler![
             commands::get_context,
             commands::get_file_tree,
+            commands::get_tree_children,
+            commands::set_tree_sort,
             commands::read_file,
             commands::save_file,
             commands::get_opened_file,
             commands::set_current_root,
             commands::get_current_root,
+            commands::open_workspace,
+            commands::save_workspace,
+            commands::close_workspace,
+            commands::get_workspace,
             commands::create_file,
             commands::rename_file,
+            commands::preview_rename,
+            commands::rename_with_links,
             commands::delete_file,
             commands::watch_file,
             commands::unwatch_file,
+            commands::watch_tree,
+            commands::unwatch_tree,
+            commands::search_workspace,
+            commands::cancel_search,
+            commands::ranked_search,
+            commands::quick_open,
+            commands::search_headings,
+            commands::get_outline,
+            commands::get_backlinks,
+            commands::get_outgoing_links,
+            commands::resolve_wikilink,
+            commands::complete_link,
+            commands::check_links,
+            commands::get_asset_report,
+            commands::trash_unused_assets,
+            commands::list_trash,
+            commands::restore_trashed,
+            commands::preview_replace,
+            commands::apply_replace,
             commands::dump_state_to_file,
             commands::bench_ready,
         ])
@@ -110,7 +163,10 @@ pub fn run() {
 
             // Fallback: scan raw args for a .md file path (handles tauri dev extra flags)
             if file_path.is_none() {
-                for arg in std::env::args().skip(1) {
+                for arg in std::env::args_os()
+                    .skip(1)
+                    .filter_map(|a| a.into_string().ok())
+                {
                     if arg.starts_with('-') {
                         continue;
                     }
@@ -153,13 +209,25 @@ pub fn run() {
                             .ok()
                             .and_then(|g| g.clone());
 
+                        // Files inside any root of the open workspace belong here too
+                        let in_workspace = app
+                            .state::<CurrentWorkspace>()
+                            .0
+                            .lock()
+                            .ok()
+                            .is_some_and(|ws| {
+                                ws.as_ref()
+                                    .is_some_and(|ws| ws.member_root(&path).is_some())
+                            });
+
                         // Decide: same root → switch file, different → new window
                         eprintln!("[mdcat] new_root={:?}, current_root={:?}", new_root, current_root);
-                        let same_root = match (&new_root, &current_root) {
-                            (Some(new), Some(cur)) => new == cur,
-                            (None, None) => true, // both non-git, reuse window
-                            _ => false,
-                        };
+                        let same_root = in_workspace
+                            || match (&new_root, &current_root) {
+                                (Some(new), Some(cur)) => new == cur,
+                                (None, None) => true, // both non-git, reuse window
+                                _ => false,
+                            };
                         eprintln!("[mdcat] same_root={}", same_root);
 
                         if same_root || current_root.is_none() {
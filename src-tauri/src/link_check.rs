@@ -0,0 +1,205 @@
+//! Broken link report for a root: every relative link must reach an existing
+//! file, every wiki link exactly one page, and every `#fragment` into a
+//! markdown file must name one of its headings (GitHub slugs) or HTML
+//! anchors. Also runs headless as `mdcat check-links [root]` for CI.
+
+use crate::config::{self, TreeConfig};
+use crate::heading_index;
+use crate::link_graph::{self, Link};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BrokenReason {
+    /// Nothing exists at the link's path.
+    MissingFile,
+    /// The file exists but has no heading or anchor matching the fragment.
+    MissingAnchor,
+    /// A wiki link whose page name matches several files.
+    Ambiguous,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BrokenLink {
+    /// File containing the link.
+    pub path: String,
+    pub rel_path: String,
+    /// 1-based line and char column of the link start.
+    pub line: usize,
+    pub column: usize,
+    /// Destination as written.
+    pub target: String,
+    pub reason: BrokenReason,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkReport {
+    pub root: String,
+    pub files_checked: usize,
+    pub links_checked: usize,
+    pub broken: Vec<BrokenLink>,
+}
+
+/// `id="..."` / `name="..."` attribute values, for `<a name="x">` style anchors.
+fn html_anchors(content: &str) -> impl Iterator<Item = &str> {
+    ["id=", "name="].into_iter().flat_map(move |attr| {
+        content.match_indices(attr).filter_map(move |(i, _)| {
+            let preceded = content[..i].ends_with(char::is_whitespace);
+            let rest = &content[i + attr.len()..];
+            let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+            let value = &rest[1..];
+            preceded.then(|| &value[..value.find(quote).unwrap_or(0)])
+        })
+    })
+}
+
+/// Anchors a markdown file defines, lowercased; `None` if it can't be read.
+fn anchors(path: &Path) -> Option<HashSet<String>> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let headings = heading_index::parse(&content).into_iter().map(|h| h.slug);
+    let html = html_anchors(&content).map(str::to_lowercase);
+    Some(headings.chain(html).collect())
+}
+
+/// Why `link` is broken, if it is. `anchors_of` caches each target's anchors.
+fn check_link(
+    link: &Link,
+    config: &TreeConfig,
+    anchors_of: &mut HashMap<PathBuf, Option<HashSet<String>>>,
+) -> Option<BrokenReason> {
+    let path = match &link.path {
+        Some(path) => PathBuf::from(path),
+        None if !link.kind.is_wiki() => return None,
+        None if link.candidates.is_empty() => return Some(BrokenReason::MissingFile),
+        None => return Some(BrokenReason::Ambiguous),
+    };
+    if !path.exists() {
+        return Some(BrokenReason::MissingFile);
+    }
+    let fragment = link.fragment.as_deref().filter(|f| !f.is_empty())?;
+    if !config.has_markdown_ext(&path) || (link.kind.is_wiki() && fragment.starts_with('^')) {
+        // line anchors into other files, or `[[page#^block]]` references
+        return None;
+    }
+    // wiki links name the heading itself; GitHub slugs are lowercase and
+    // its anchor lookup ignores case
+    let wanted = if link.kind.is_wiki() {
+        heading_index::slug(fragment.trim())
+    } else {
+        fragment.to_lowercase()
+    };
+    let known = anchors_of.entry(path).or_insert_with_key(|p| anchors(p));
+    let missing = known.as_ref().is_some_and(|a| !a.contains(&wanted));
+    missing.then_some(BrokenReason::MissingAnchor)
+}
+
+/// Check every link in every markdown file under `root`.
+pub fn check(root: &Path, config: &TreeConfig) -> LinkReport {
+    let mut files: Vec<(PathBuf, Vec<Link>)> = link_graph::with_links(root, config, |files| {
+        files
+            .iter()
+            .map(|(path, links)| (path.clone(), links.clone()))
+            .collect()
+    });
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut report = LinkReport {
+        root: root.to_string_lossy().to_string(),
+        files_checked: files.len(),
+        links_checked: 0,
+        broken: Vec::new(),
+    };
+    let mut anchors_of = HashMap::new();
+    for (path, links) in &files {
+        for link in links
+            .iter()
+            .filter(|l| l.path.is_some() || l.kind.is_wiki())
+        {
+            report.links_checked += 1;
+            let Some(reason) = check_link(link, config, &mut anchors_of) else {
+                continue;
+            };
+            report.broken.push(BrokenLink {
+                path: path.to_string_lossy().to_string(),
+                rel_path: path
+                    .strip_prefix(root)
+                    .unwrap_or(path)
+                    .to_string_lossy()
+                    .to_string(),
+                line: link.line,
+                column: link.column,
+                target: link.target.clone(),
+                reason,
+            });
+        }
+    }
+    report
+}
+
+const USAGE: &str = "Usage: mdcat check-links [ROOT] [--json]";
+
+/// `mdcat check-links [ROOT] [--json]`: print broken links as
+/// `file:line:column: target (reason)` (or the report as JSON) and return the
+/// exit code: 0 when clean, 1 when links are broken, 2 on bad usage.
+pub fn run_cli(args: &[String]) -> i32 {
+    let mut json = false;
+    let mut root: Option<PathBuf> = None;
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return 0;
+            }
+            a if a.starts_with('-') || root.is_some() => {
+                eprintln!("{}", USAGE);
+                return 2;
+            }
+            a => root = Some(PathBuf::from(a)),
+        }
+    }
+    let root = root.unwrap_or_else(|| PathBuf::from("."));
+    let root = match std::path::absolute(&root) {
+        Ok(root) if root.is_dir() => root,
+        _ => {
+            eprintln!("mdcat: not a directory: {}", root.display());
+            return 2;
+        }
+    };
+
+    let report = check(&root, &config::load_for_root(&root).config);
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(out) => println!("{}", out),
+            Err(e) => {
+                eprintln!("mdcat: {}", e);
+                return 2;
+            }
+        }
+    } else {
+        for b in &report.broken {
+            let reason = match b.reason {
+                BrokenReason::MissingFile => "missing file",
+                BrokenReason::MissingAnchor => "missing anchor",
+                BrokenReason::Ambiguous => "ambiguous page name",
+            };
+            println!(
+                "{}:{}:{}: {} ({})",
+                b.rel_path, b.line, b.column, b.target, reason
+            );
+        }
+        eprintln!(
+            "{} broken of {} links in {} files",
+            report.broken.len(),
+            report.links_checked,
+            report.files_checked
+        );
+    }
+    if report.broken.is_empty() {
+        0
+    } else {
+        1
+    }
+}
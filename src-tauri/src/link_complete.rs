@@ -0,0 +1,210 @@
+//! Completions for a link destination being typed in the editor: markdown
+//! files and assets of the root as paths relative to the file being edited,
+//! or, after a `#`, the headings of the chosen file as slugs. Files close to
+//! the editor's file and recently opened ones rank first among equals.
+
+use crate::config::TreeConfig;
+use crate::file_tree;
+use crate::heading_index;
+use crate::link_graph;
+use crate::quick_open::{self, Text};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Results returned when the caller doesn't ask for a number.
+pub const DEFAULT_LIMIT: usize = 50;
+
+/// How long a root's asset list is reused. The tree watcher only reports
+/// markdown files, so assets are rescanned instead of invalidated.
+const ASSET_TTL: Duration = Duration::from_secs(5);
+
+/// Bonus for a candidate in the editor's own directory, shrinking with each
+/// `..` and each subdirectory in between.
+const PROXIMITY_MAX: i32 = 32;
+const PROXIMITY_UP: i32 = 8;
+const PROXIMITY_DOWN: i32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompletionKind {
+    File,
+    Asset,
+    Heading,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkCompletion {
+    /// Destination to insert: a relative path, `path#slug` or `#slug`.
+    pub label: String,
+    pub kind: CompletionKind,
+    pub path: String,
+    /// File title or heading text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub score: i32,
+}
+
+/// When a root's assets were scanned, and what was found.
+type AssetScan = (Instant, Arc<Vec<PathBuf>>);
+
+static ASSETS: Mutex<Option<HashMap<PathBuf, AssetScan>>> = Mutex::new(None);
+
+/// Asset files under `root`, rescanned at most every `ASSET_TTL`.
+fn assets(root: &Path, config: &TreeConfig) -> Arc<Vec<PathBuf>> {
+    if let Ok(lock) = ASSETS.lock() {
+        if let Some((at, files)) = lock.as_ref().and_then(|a| a.get(root)) {
+            if at.elapsed() < ASSET_TTL {
+                return Arc::clone(files);
+            }
+        }
+    }
+    let files = Arc::new(file_tree::scan_asset_files(root, config));
+    if let Ok(mut lock) = ASSETS.lock() {
+        lock.get_or_insert_with(HashMap::new)
+            .insert(root.to_path_buf(), (Instant::now(), Arc::clone(&files)));
+    }
+    files
+}
+
+fn proximity(label: &str) -> i32 {
+    let ups = label.split('/').filter(|s| *s == "..").count() as i32;
+    let downs = label.split('/').count() as i32 - ups - 1;
+    (PROXIMITY_MAX - ups * PROXIMITY_UP - downs * PROXIMITY_DOWN).max(0)
+}
+
+/// Headings of the file `written` (resolved from `from`; empty for `from`
+/// itself) whose text matches `fragment`, in document order among equals.
+fn heading_completions(
+    root: &Path,
+    from: &Path,
+    written: &str,
+    fragment: &str,
+) -> Vec<LinkCompletion> {
+    let target = if written.is_empty() {
+        from.to_path_buf()
+    } else {
+        match link_graph::resolve(written, from, root).0 {
+            Some(target) => target,
+            None => return Vec::new(),
+        }
+    };
+    let Ok(headings) = heading_index::outline(&target) else {
+        return Vec::new();
+    };
+    let terms = quick_open::query_terms(fragment);
+    let mut matches: Vec<(usize, LinkCompletion)> = headings
+        .into_iter()
+        .filter_map(|h| {
+            let score = if terms.is_empty() {
+                0
+            } else {
+                quick_open::match_terms(&terms, &Text::new(&h.text))?.0
+            };
+            let completion = LinkCompletion {
+                label: format!("{}#{}", written, h.slug),
+                kind: CompletionKind::Heading,
+                path: target.to_string_lossy().to_string(),
+                detail: Some(h.text),
+                score,
+            };
+            Some((h.line, completion))
+        })
+        .collect();
+    matches.sort_by(|a, b| b.1.score.cmp(&a.1.score).then(a.0.cmp(&b.0)));
+    matches.into_iter().map(|(_, c)| c).collect()
+}
+
+/// Up to `limit` destinations for a link in `from` whose destination so far
+/// is `prefix`, best first.
+pub fn complete(
+    app: &tauri::AppHandle,
+    root: &Path,
+    config: &TreeConfig,
+    from: &Path,
+    prefix: &str,
+    limit: usize,
+) -> Vec<LinkCompletion> {
+    if let Some((written, fragment)) = prefix.split_once('#') {
+        let mut matches = heading_completions(root, from, written, fragment);
+        matches.truncate(limit);
+        return matches;
+    }
+
+    // typed `./` / `../` / `/` only say where paths start; match the rest
+    // against root-relative paths
+    let terms = quick_open::query_terms(prefix.trim_start_matches(['.', '/']));
+    let rooted = prefix.starts_with('/');
+    let base = from.parent().unwrap_or(root);
+    let recency = quick_open::recency_boosts(app);
+    let label = |path: &Path| {
+        let rel = if rooted {
+            format!("/{}", link_graph::relative(path, root))
+        } else {
+            link_graph::relative(path, base)
+        };
+        link_graph::escape_destination(&rel)
+    };
+    let score = |path: &Path, label: &str, matched: i32| {
+        matched + proximity(label) + recency.get(path).copied().unwrap_or(0)
+    };
+
+    let mut matches: Vec<LinkCompletion> = quick_open::with_candidates(root, config, |files| {
+        files
+            .iter()
+            .filter(|c| c.path != from)
+            .filter_map(|c| {
+                let matched = if terms.is_empty() {
+                    0
+                } else {
+                    let by_path = quick_open::match_terms(&terms, &c.rel_text).map(|m| m.0);
+                    let by_title = c
+                        .title_text
+                        .as_ref()
+                        .and_then(|t| quick_open::match_terms(&terms, t))
+                        .map(|m| m.0);
+                    by_path.max(by_title)?
+                };
+                let label = label(&c.path);
+                Some(LinkCompletion {
+                    score: score(&c.path, &label, matched),
+                    label,
+                    kind: CompletionKind::File,
+                    path: c.path.to_string_lossy().to_string(),
+                    detail: c.title.clone(),
+                })
+            })
+            .collect()
+    });
+
+    for path in assets(root, config).iter() {
+        let matched = if terms.is_empty() {
+            0
+        } else {
+            let rel = path.strip_prefix(root).unwrap_or(path).to_string_lossy();
+            match quick_open::match_terms(&terms, &Text::new(&rel)) {
+                Some((matched, _)) => matched,
+                None => continue,
+            }
+        };
+        let label = label(path);
+        matches.push(LinkCompletion {
+            score: score(path, &label, matched),
+            label,
+            kind: CompletionKind::Asset,
+            path: path.to_string_lossy().to_string(),
+            detail: None,
+        });
+    }
+
+    matches.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(a.label.len().cmp(&b.label.len()))
+            .then_with(|| a.label.cmp(&b.label))
+    });
+    matches.truncate(limit);
+    matches
+}
@@ -0,0 +1,829 @@
+//! Links between the markdown files of a root: inline links, images,
+//! reference definitions and `[[wiki links]]` / `![[embeds]]`, each resolved
+//! to the file it points at, so a file's backlinks can be listed.
+//!
+//! Wiki links name a page rather than a path: they resolve against file
+//! stems and frontmatter `aliases` anywhere in the root (or a `dir/page`
+//! path suffix), and a name several files share is reported as ambiguous.
+//! Attachments like `![[diagram.png]]` are looked for next to the note, at
+//! the root, then among the root's asset files by path suffix.
+//!
+//! A root's links are parsed the first time they're asked for and then
+//! follow the tree watcher's diffs; roots no longer watched are dropped.
+
+use crate::config::TreeConfig;
+use crate::file_meta::{self, FenceState};
+use crate::file_tree::{self, ScanScope};
+use crate::heading_index;
+use crate::tree_watcher::{self, TreeDiff};
+use serde::Serialize;
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    /// `[text](dest)`
+    Inline,
+    /// `![alt](dest)`
+    Image,
+    /// `[label]: dest`, the definition reference-style links point at.
+    Reference,
+    /// `[[page#heading|text]]`
+    Wiki,
+    /// `![[page]]`
+    Embed,
+}
+
+impl LinkKind {
+    pub fn is_wiki(self) -> bool {
+        matches!(self, LinkKind::Wiki | LinkKind::Embed)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Link {
+    pub kind: LinkKind,
+    /// Link text, image alt text or reference label.
+    pub text: String,
+    /// Destination as written (`page#heading` for wiki links).
+    pub target: String,
+    /// Local file the destination resolves to (the source itself for a bare
+    /// `#fragment`); `None` for URLs and unresolved wiki links.
+    pub path: Option<String>,
+    /// `#fragment` without the `#`; heading text rather than a slug for wiki links.
+    pub fragment: Option<String>,
+    /// Every file a wiki link's page name matches, when more than one does.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<String>,
+    /// 1-based line and char column of the link start.
+    pub line: usize,
+    pub column: usize,
+    /// Byte range of `target` in the file, for rewriting it.
+    #[serde(skip)]
+    pub span: Range<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Backlink {
+    /// File containing the link.
+    pub source: String,
+    /// `source` relative to its root.
+    pub rel_path: String,
+    #[serde(flatten)]
+    pub link: Link,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WikiResolution {
+    /// The file the link opens, if its page name picks one.
+    pub path: Option<String>,
+    /// Heading text after `#`, and its anchor in `path`.
+    pub fragment: Option<String>,
+    pub slug: Option<String>,
+    /// Every file the page name matches, when more than one does.
+    pub candidates: Vec<String>,
+}
+
+/// Outgoing links per file of one root.
+pub type FileLinks = HashMap<PathBuf, Vec<Link>>;
+
+struct RootLinks {
+    files: FileLinks,
+    /// Frontmatter `aliases` per file, for wiki links.
+    aliases: HashMap<PathBuf, Vec<String>>,
+    /// Whether wiki links are resolved against the current files and aliases.
+    resolved: bool,
+}
+
+static INDEX: Mutex<Option<HashMap<PathBuf, RootLinks>>> = Mutex::new(None);
+
+/// Diffs that found their root not loaded; a build overlapping one isn't
+/// kept, as it may predate the change.
+static MISSED_DIFFS: AtomicU64 = AtomicU64::new(0);
+
+/// `scheme:` (http, mailto...) or protocol-relative `//host` destinations.
+fn is_external(dest: &str) -> bool {
+    if dest.starts_with("//") {
+        return true;
+    }
+    let Some((scheme, _)) = dest.split_once(':') else {
+        return false;
+    };
+    let mut chars = scheme.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+/// `path` as a bare link destination: `%` and spaces escaped, and
+/// parentheses too when unbalanced, since either would end it early.
+pub fn escape_destination(path: &str) -> String {
+    let escaped = path.replace('%', "%25").replace(' ', "%20");
+    let mut depth = 0i32;
+    let balanced = path.chars().all(|c| {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        depth >= 0
+    }) && depth == 0;
+    if balanced {
+        escaped
+    } else {
+        escaped.replace('(', "%28").replace(')', "%29")
+    }
+}
+
+/// Decode `%XX` escapes (`my%20doc.md` -> `my doc.md`).
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(byte) = s
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// `target` relative to directory `base`, `/`-separated.
+pub fn relative(target: &Path, base: &Path) -> String {
+    let target: Vec<Component> = target.components().collect();
+    let base: Vec<Component> = base.components().collect();
+    let common = target.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    let ups = std::iter::repeat_n("..".to_string(), base.len() - common);
+    let downs = target[common..]
+        .iter()
+        .map(|c| c.as_os_str().to_string_lossy().to_string());
+    ups.chain(downs).collect::<Vec<_>>().join("/")
+}
+
+/// The file and fragment a destination written in `source` points at:
+/// `/`-rooted paths are taken from `root`, others from `source`'s directory.
+pub fn resolve(dest: &str, source: &Path, root: &Path) -> (Option<PathBuf>, Option<String>) {
+    if is_external(dest) {
+        return (None, None);
+    }
+    let (path, fragment) = match dest.split_once('#') {
+        Some((path, fragment)) => (path, Some(percent_decode(fragment))),
+        None => (dest, None),
+    };
+    let path = path.split_once('?').map_or(path, |(p, _)| p);
+    if path.is_empty() {
+        return (Some(source.to_path_buf()), fragment);
+    }
+    let path = percent_decode(path);
+    let resolved = match path.strip_prefix('/') {
+        Some(rooted) => root.join(rooted),
+        None => source.parent().unwrap_or(root).join(&path),
+    };
+    (Some(file_tree::normalize(&resolved)), fragment)
+}
+
+/// End of the `[...]` starting at `open` (index of the matching `]`).
+fn closing_bracket(line: &str, open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut escaped = false;
+    for (i, c) in line[open..].char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Destination at the start of `rest` (just after `(` or `]:`), as a byte
+/// range into `rest`: `<...>`, or a run without spaces and unbalanced `)`.
+fn destination(rest: &str) -> Option<Range<usize>> {
+    let start = rest.len() - rest.trim_start().len();
+    let rest = &rest[start..];
+    if let Some(inner) = rest.strip_prefix('<') {
+        let end = inner.find(['>', '\n'])?;
+        return (inner.as_bytes()[end] == b'>').then(|| start + 1..start + 1 + end);
+    }
+    let mut depth = 0;
+    for (i, c) in rest.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(start..start + i),
+            ')' => depth -= 1,
+            c if c.is_whitespace() => return Some(start..start + i),
+            _ => {}
+        }
+    }
+    Some(start..start + rest.len())
+}
+
+/// `[label]: dest` at the start of `line` (not a `[^footnote]:`).
+fn reference_definition(line: &str) -> Option<(&str, Range<usize>)> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 || !line[indent..].starts_with('[') || line[indent..].starts_with("[^") {
+        return None;
+    }
+    let close = closing_bracket(line, indent)?;
+    let after = line[close + 1..].strip_prefix(':')?;
+    let dest = destination(after)?;
+    let offset = close + 2;
+    (!dest.is_empty()).then(|| {
+        (
+            &line[indent + 1..close],
+            offset + dest.start..offset + dest.end,
+        )
+    })
+}
+
+/// Start of the `]]` closing a wiki link opened at `open`. Not one when
+/// followed by `(`: `[[x]](dest)` is an inline link whose text is `[x]`.
+fn wiki_close(line: &str, open: usize) -> Option<usize> {
+    if !line[open..].starts_with("[[") {
+        return None;
+    }
+    let end = open + 2 + line[open + 2..].find("]]")?;
+    (!line[end + 2..].starts_with('(')).then_some(end)
+}
+
+/// Inline links and images of one line, skipping code spans. Link text is
+/// scanned too, so an image inside a link is found as well.
+fn inline_links(line: &str) -> Vec<(LinkKind, usize, &str, Range<usize>)> {
+    let mut links = Vec::new();
+    let bytes = line.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'`' => {
+                let run = bytes[i..].iter().take_while(|b| **b == b'`').count();
+                let fence = &line[i..i + run];
+                i = match line[i + run..].find(fence) {
+                    Some(end) => i + run + end + run,
+                    None => i + run,
+                };
+            }
+            b'[' => {
+                if let Some(end) = wiki_close(line, i) {
+                    let embed = i > 0 && bytes[i - 1] == b'!';
+                    let (kind, start) = if embed {
+                        (LinkKind::Embed, i - 1)
+                    } else {
+                        (LinkKind::Wiki, i)
+                    };
+                    let inner = &line[i + 2..end];
+                    let target_end = i + 2 + inner.find('|').unwrap_or(inner.len());
+                    let text = inner.split_once('|').map_or(inner, |(_, t)| t);
+                    links.push((kind, start, text, i + 2..target_end));
+                    i = end + 2;
+                    continue;
+                }
+                let image = i > 0 && bytes[i - 1] == b'!' && (i < 2 || bytes[i - 2] != b'\\');
+                if let Some(close) = closing_bracket(line, i) {
+                    if line[close + 1..].starts_with('(') {
+                        if let Some(dest) = destination(&line[close + 2..]) {
+                            let kind = if image {
+                                LinkKind::Image
+                            } else {
+                                LinkKind::Inline
+                            };
+                            let start = if image { i - 1 } else { i };
+                            let span = close + 2 + dest.start..close + 2 + dest.end;
+                            links.push((kind, start, &line[i + 1..close], span));
+                        }
+                    }
+                }
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    links
+}
+
+/// Links of a markdown document in order, skipping fenced code and
+/// frontmatter. `source` is the document's path, used with `root` to
+/// resolve destinations.
+pub fn parse(content: &str, source: &Path, root: &Path) -> Vec<Link> {
+    let (_, body, body_start) = file_meta::split_frontmatter(content);
+    let mut offset = content.len() - body.len();
+    let mut links = Vec::new();
+    let mut fences = FenceState::default();
+
+    for (i, raw) in body.split_inclusive('\n').enumerate() {
+        let line_start = offset;
+        offset += raw.len();
+        let line = raw.trim_end_matches(['\n', '\r']);
+        if fences.is_code(line) {
+            continue;
+        }
+        let found: Vec<(LinkKind, usize, &str, Range<usize>)> = match reference_definition(line) {
+            Some((label, dest)) => {
+                let start = line.len() - line.trim_start().len();
+                vec![(LinkKind::Reference, start, label, dest)]
+            }
+            None => inline_links(line),
+        };
+        for (kind, start, text, dest) in found {
+            let target = &line[dest.clone()];
+            if target.is_empty() {
+                continue;
+            }
+            let (path, fragment) = if kind.is_wiki() {
+                // resolved against the root's page names later
+                let fragment = target.split_once('#').map(|(_, f)| f.to_string());
+                (None, fragment)
+            } else {
+                resolve(target, source, root)
+            };
+            links.push(Link {
+                kind,
+                text: text.to_string(),
+                target: target.to_string(),
+                path: path.map(|p| p.to_string_lossy().to_string()),
+                fragment,
+                candidates: Vec::new(),
+                line: body_start + i + 1,
+                column: line[..start].chars().count() + 1,
+                span: line_start + dest.start..line_start + dest.end,
+            });
+        }
+    }
+    links
+}
+
+/// Lowercased page names wiki links can use for each file: its stem and
+/// its aliases, plus root-relative paths without extension for `dir/page`.
+struct WikiNames {
+    by_name: HashMap<String, Vec<PathBuf>>,
+    by_path: Vec<(String, PathBuf)>,
+    /// Root-relative asset paths, scanned the first time an attachment
+    /// isn't found next to its note or at the root.
+    assets: OnceCell<Vec<(String, PathBuf)>>,
+}
+
+impl WikiNames {
+    fn new(root: &Path, rl: &RootLinks) -> Self {
+        let mut by_name: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        let mut by_path = Vec::new();
+        for path in rl.files.keys() {
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            let aliases = rl.aliases.get(path).into_iter().flatten();
+            for name in std::iter::once(stem.as_ref()).chain(aliases.map(String::as_str)) {
+                let entry = by_name.entry(name.to_lowercase()).or_default();
+                if !entry.contains(path) {
+                    entry.push(path.clone());
+                }
+            }
+            let rel = path.strip_prefix(root).unwrap_or(path).with_extension("");
+            by_path.push((rel.to_string_lossy().to_lowercase(), path.clone()));
+        }
+        for paths in by_name.values_mut() {
+            paths.sort();
+        }
+        by_path.sort();
+        WikiNames {
+            by_name,
+            by_path,
+            assets: OnceCell::new(),
+        }
+    }
+
+    fn assets(&self, root: &Path, config: &TreeConfig) -> &[(String, PathBuf)] {
+        self.assets.get_or_init(|| {
+            file_tree::scan_asset_files(root, config)
+                .into_iter()
+                .map(|path| {
+                    let rel = path.strip_prefix(root).unwrap_or(&path);
+                    (rel.to_string_lossy().to_lowercase(), path)
+                })
+                .collect()
+        })
+    }
+
+    /// Files `page` (the part of a wiki link before `#`) can mean.
+    fn lookup(&self, page: &str, source: &Path, root: &Path, config: &TreeConfig) -> Vec<PathBuf> {
+        let page = page.trim();
+        if page.is_empty() {
+            return vec![source.to_path_buf()];
+        }
+        let as_path = Path::new(page);
+        let name = if config.has_markdown_ext(as_path) {
+            as_path.with_extension("").to_string_lossy().to_lowercase()
+        } else if as_path.extension().is_some() {
+            // an attachment such as `![[diagram.png]]`
+            let rel = page.trim_start_matches('/');
+            let dir = source.parent().unwrap_or(root);
+            if let Some(near) = [dir.join(rel), root.join(rel)]
+                .into_iter()
+                .find(|p| p.exists())
+            {
+                return vec![near];
+            }
+            let rel = rel.to_lowercase();
+            let suffix = format!("/{}", rel);
+            return self
+                .assets(root, config)
+                .iter()
+                .filter(|(asset, _)| *asset == rel || asset.ends_with(&suffix))
+                .map(|(_, p)| p.clone())
+                .collect();
+        } else {
+            page.to_lowercase()
+        };
+        match name.strip_prefix('/') {
+            Some(rooted) => self
+                .by_path
+                .iter()
+                .filter(|(rel, _)| rel == rooted)
+                .map(|(_, p)| p.clone())
+                .collect(),
+            None if name.contains('/') => {
+                let suffix = format!("/{}", name);
+                self.by_path
+                    .iter()
+                    .filter(|(rel, _)| *rel == name || rel.ends_with(&suffix))
+                    .map(|(_, p)| p.clone())
+                    .collect()
+            }
+            None => self.by_name.get(&name).cloned().unwrap_or_default(),
+        }
+    }
+}
+
+/// The one file among `candidates` a link from `source` means: the only
+/// candidate, else the only one beside `source`.
+fn pick(candidates: &[PathBuf], source: &Path) -> Option<PathBuf> {
+    match candidates {
+        [only] => Some(only.clone()),
+        _ => {
+            let mut near = candidates.iter().filter(|c| c.parent() == source.parent());
+            match (near.next(), near.next()) {
+                (Some(one), None) => Some(one.clone()),
+                _ => None,
+            }
+        }
+    }
+}
+
+fn resolve_wiki(
+    link: &mut Link,
+    names: &WikiNames,
+    source: &Path,
+    root: &Path,
+    config: &TreeConfig,
+) {
+    let page = link.target.split('#').next().unwrap_or_default();
+    let candidates = names.lookup(page, source, root, config);
+    link.path = pick(&candidates, source).map(|p| p.to_string_lossy().to_string());
+    link.candidates = match candidates.len() {
+        0 | 1 => Vec::new(),
+        _ => candidates
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect(),
+    };
+}
+
+/// Links and frontmatter aliases of the file at `path`.
+fn index_file(path: &Path, root: &Path) -> (Vec<Link>, Vec<String>) {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return (Vec::new(), Vec::new());
+    };
+    let aliases = match file_meta::split_frontmatter(&content) {
+        (Some(fm), _, _) => file_meta::frontmatter_list(fm, "aliases"),
+        _ => Vec::new(),
+    };
+    (parse(&content, path, root), aliases)
+}
+
+impl RootLinks {
+    fn build(files: Vec<PathBuf>, root: &Path) -> Self {
+        let mut rl = RootLinks {
+            files: HashMap::new(),
+            aliases: HashMap::new(),
+            resolved: false,
+        };
+        for path in files {
+            rl.insert(path, root);
+        }
+        rl
+    }
+
+    fn insert(&mut self, path: PathBuf, root: &Path) {
+        let indexed = index_file(&path, root);
+        self.put(path, indexed);
+    }
+
+    fn put(&mut self, path: PathBuf, (links, aliases): (Vec<Link>, Vec<String>)) {
+        if aliases.is_empty() {
+            self.aliases.remove(&path);
+        } else {
+            self.aliases.insert(path.clone(), aliases);
+        }
+        self.files.insert(path, links);
+    }
+
+    fn remove(&mut self, path: &Path) {
+        self.files.remove(path);
+        self.aliases.remove(path);
+    }
+
+    /// Point every wiki link at its page under the current names.
+    fn resolve(&mut self, root: &Path, config: &TreeConfig) {
+        if self.resolved {
+            return;
+        }
+        let names = WikiNames::new(root, self);
+        for (source, links) in self.files.iter_mut() {
+            for link in links.iter_mut().filter(|l| l.kind.is_wiki()) {
+                resolve_wiki(link, &names, source, root, config);
+            }
+        }
+        self.resolved = true;
+    }
+}
+
+/// Apply a `tree-changed` diff to the root's links, if they're loaded.
+/// Changed files are parsed before the lock is taken again.
+pub fn apply_diff(diff: &TreeDiff) {
+    let root = Path::new(&diff.root);
+    let modified: Vec<&String> = {
+        let Ok(lock) = INDEX.lock() else {
+            return;
+        };
+        let Some(rl) = lock.as_ref().and_then(|i| i.get(root)) else {
+            MISSED_DIFFS.fetch_add(1, Ordering::SeqCst);
+            return;
+        };
+        // `modified` also carries non-markdown files such as `mkdocs.yml`
+        diff.modified
+            .iter()
+            .filter(|p| rl.files.contains_key(Path::new(p)))
+            .collect()
+    };
+    let parsed: Vec<_> = diff
+        .added
+        .iter()
+        .chain(diff.renamed.iter().map(|r| &r.to))
+        .chain(modified)
+        .map(|p| (PathBuf::from(p), index_file(Path::new(p), root)))
+        .collect();
+
+    let Ok(mut lock) = INDEX.lock() else {
+        return;
+    };
+    let Some(rl) = lock.as_mut().and_then(|i| i.get_mut(root)) else {
+        MISSED_DIFFS.fetch_add(1, Ordering::SeqCst);
+        return;
+    };
+    for path in &diff.removed {
+        rl.remove(Path::new(path));
+    }
+    for renamed in &diff.renamed {
+        rl.remove(Path::new(&renamed.from));
+    }
+    for (path, indexed) in parsed {
+        rl.put(path, indexed);
+    }
+    // page names may have come or gone
+    rl.resolved = false;
+}
+
+/// Drop loaded links of roots not in `keep`.
+pub fn retain(keep: &[PathBuf]) {
+    if let Ok(mut lock) = INDEX.lock() {
+        if let Some(index) = lock.as_mut() {
+            index.retain(|root, _| keep.contains(root));
+        }
+    }
+}
+
+/// Run `f` on the root's links with wiki links resolved. Roots without a
+/// ready watcher index are parsed for this call but not kept. Files are
+/// parsed without the lock held.
+fn with_root<R>(root: &Path, config: &TreeConfig, f: impl FnOnce(&RootLinks) -> R) -> R {
+    let missed = MISSED_DIFFS.load(Ordering::SeqCst);
+    let loaded = INDEX
+        .lock()
+        .is_ok_and(|l| l.as_ref().is_some_and(|i| i.contains_key(root)));
+    // (links, whether they follow the watcher and can be kept)
+    let mut built = (!loaded).then(|| match tree_watcher::cached_files(root, root) {
+        Some(files) => (RootLinks::build(files, root), true),
+        None => {
+            let files = file_tree::scan_md_files(root, config, ScanScope::All);
+            (RootLinks::build(files, root), false)
+        }
+    });
+
+    if let Ok(mut lock) = INDEX.lock() {
+        let index = lock.get_or_insert_with(HashMap::new);
+        let fresh = MISSED_DIFFS.load(Ordering::SeqCst) == missed;
+        if let Some((rl, _)) = built.take_if(|(_, keep)| *keep && fresh) {
+            index.entry(root.to_path_buf()).or_insert(rl);
+        }
+        if let Some(rl) = index.get_mut(root) {
+            rl.resolve(root, config);
+            return f(rl);
+        }
+    }
+    let mut rl = match built {
+        Some((rl, _)) => rl,
+        None => RootLinks::build(file_tree::scan_md_files(root, config, ScanScope::All), root),
+    };
+    rl.resolve(root, config);
+    f(&rl)
+}
+
+/// Run `f` on the links of every markdown file under `root`.
+pub fn with_links<R>(root: &Path, config: &TreeConfig, f: impl FnOnce(&FileLinks) -> R) -> R {
+    with_root(root, config, |rl| f(&rl.files))
+}
+
+/// `parse`, with wiki links resolved against the root's page names.
+pub fn parse_in_root(content: &str, source: &Path, root: &Path, config: &TreeConfig) -> Vec<Link> {
+    let mut links = parse(content, source, root);
+    if links.iter().any(|l| l.kind.is_wiki()) {
+        with_root(root, config, |rl| {
+            let names = WikiNames::new(root, rl);
+            for link in links.iter_mut().filter(|l| l.kind.is_wiki()) {
+                resolve_wiki(link, &names, source, root, config);
+            }
+        });
+    }
+    links
+}
+
+/// Links of the file at `path`, straight from disk.
+pub fn outgoing(path: &Path, root: &Path, config: &TreeConfig) -> Result<Vec<Link>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(parse_in_root(&content, path, root, config))
+}
+
+/// Where the wiki link `text` (`[[page#heading|label]]`, brackets optional)
+/// written in `source` leads.
+pub fn resolve_wikilink(
+    root: &Path,
+    config: &TreeConfig,
+    source: &Path,
+    text: &str,
+) -> WikiResolution {
+    let inner = text.trim().trim_start_matches('!');
+    let inner = inner
+        .strip_prefix("[[")
+        .and_then(|t| t.strip_suffix("]]"))
+        .unwrap_or(inner);
+    let target = inner.split('|').next().unwrap_or_default();
+    let (page, fragment) = match target.split_once('#') {
+        Some((page, fragment)) => (page, Some(fragment.to_string())),
+        None => (target, None),
+    };
+    let candidates = with_root(root, config, |rl| {
+        WikiNames::new(root, rl).lookup(page, source, root, config)
+    });
+    let path = pick(&candidates, source);
+    WikiResolution {
+        path: path.map(|p| p.to_string_lossy().to_string()),
+        slug: fragment.as_deref().map(|f| heading_index::slug(f.trim())),
+        fragment,
+        candidates: match candidates.len() {
+            0 | 1 => Vec::new(),
+            _ => candidates
+                .iter()
+                .map(|p| p.to_string_lossy().to_string())
+                .collect(),
+        },
+    }
+}
+
+/// Links from other files under `root` that point at `path`, by file then line.
+pub fn backlinks(root: &Path, config: &TreeConfig, path: &Path) -> Vec<Backlink> {
+    let target = file_tree::normalize(path);
+    let mut backlinks: Vec<Backlink> = with_links(root, config, |files| {
+        files
+            .iter()
+            .filter(|(source, _)| source.as_path() != target)
+            .flat_map(|(source, links)| {
+                links
+                    .iter()
+                    .filter(|l| l.path.as_deref().is_some_and(|p| Path::new(p) == target))
+                    .map(move |link| Backlink {
+                        source: source.to_string_lossy().to_string(),
+                        rel_path: source
+                            .strip_prefix(root)
+                            .unwrap_or(source)
+                            .to_string_lossy()
+                            .to_string(),
+                        link: link.clone(),
+                    })
+            })
+            .collect()
+    });
+    backlinks.sort_by(|a, b| {
+        a.rel_path
+            .cmp(&b.rel_path)
+            .then(a.link.line.cmp(&b.link.line))
+            .then(a.link.column.cmp(&b.link.column))
+    });
+    backlinks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bracketed_link_text_is_not_a_wiki_link() {
+        let links = parse(
+            "See [[v2]](changelog.md) and [[Page]]",
+            Path::new("/r/a.md"),
+            Path::new("/r"),
+        );
+        let found: Vec<(LinkKind, &str, &str)> = links
+            .iter()
+            .map(|l| (l.kind, l.text.as_str(), l.target.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (LinkKind::Inline, "[v2]", "changelog.md"),
+                (LinkKind::Wiki, "Page", "Page"),
+            ]
+        );
+    }
+
+    #[test]
+    fn escaped_destinations_round_trip() {
+        for (path, escaped) in [
+            ("notes/my doc.md", "notes/my%20doc.md"),
+            ("100%.md", "100%25.md"),
+            ("f(x).md", "f(x).md"),
+            ("smile :).md", "smile%20:%29.md"),
+            ("(draft.md", "%28draft.md"),
+        ] {
+            assert_eq!(escape_destination(path), escaped);
+            let line = format!("[x]({})", escaped);
+            let links = parse(&line, Path::new("/r/a.md"), Path::new("/r"));
+            assert_eq!(links[0].target, escaped);
+            assert_eq!(
+                links[0].path.as_deref(),
+                Some(format!("/r/{}", path).as_str())
+            );
+        }
+    }
+
+    #[test]
+    fn attachments_resolve_by_path_suffix() {
+        let root = std::env::temp_dir().join(format!("mdcat-wiki-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("notes")).unwrap();
+        std::fs::create_dir_all(root.join("attachments/img")).unwrap();
+        std::fs::write(root.join("notes/a.md"), "").unwrap();
+        std::fs::write(root.join("attachments/img/diagram.png"), "").unwrap();
+        let rl = RootLinks {
+            files: HashMap::new(),
+            aliases: HashMap::new(),
+            resolved: false,
+        };
+        let names = WikiNames::new(&root, &rl);
+        let source = root.join("notes/a.md");
+        let config = TreeConfig::default();
+        let expected = vec![root.join("attachments/img/diagram.png")];
+        for page in [
+            "diagram.png",
+            "img/Diagram.png",
+            "/attachments/img/diagram.png",
+        ] {
+            assert_eq!(
+                names.lookup(page, &source, &root, &config),
+                expected,
+                "{}",
+                page
+            );
+        }
+        assert!(names
+            .lookup("other.png", &source, &root, &config)
+            .is_empty());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,414 @@
+//! Link edits that keep a root's links working when a file or directory is
+//! moved: inbound links (inline, reference definitions, images, wiki links)
+//! are pointed at the new location, and relative links inside moved files
+//! are adjusted for their new directory. Each rewrite keeps the original's
+//! style: root-absolute or relative, a leading `./`, `%20` escapes, and any
+//! `?query` / `#fragment`. Previewed files carry a content stamp (see
+//! `workspace_replace::stamp`) so only the files approved as shown are
+//! rewritten.
+
+use crate::config::TreeConfig;
+use crate::link_graph::{self, Link};
+use crate::workspace_replace;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkEdit {
+    /// 1-based line and char column of the link start.
+    pub line: usize,
+    pub column: usize,
+    /// Destination as written, and as it will be.
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileEdits {
+    /// File as it is now (before the move).
+    pub path: String,
+    pub rel_path: String,
+    /// Content hash at preview time; pass it back to apply.
+    pub stamp: String,
+    pub edits: Vec<LinkEdit>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenamePreview {
+    pub from: String,
+    pub to: String,
+    pub files: Vec<FileEdits>,
+    pub total_edits: usize,
+}
+
+/// A file to rewrite: where it is now, where it'll be after the move, its
+/// content and the spans to replace.
+pub struct Rewrite {
+    pub path: PathBuf,
+    pub new_path: PathBuf,
+    /// Content as read, to restore if the move fails.
+    pub content: String,
+    edits: Vec<(Range<usize>, LinkEdit)>,
+}
+
+impl Rewrite {
+    pub fn stamp(&self) -> String {
+        workspace_replace::stamp(&self.content)
+    }
+
+    /// Content with every edit applied.
+    pub fn apply(&self) -> String {
+        let mut out = String::with_capacity(self.content.len());
+        let mut copied = 0;
+        for (span, edit) in &self.edits {
+            out.push_str(&self.content[copied..span.start]);
+            out.push_str(&edit.to);
+            copied = span.end;
+        }
+        out.push_str(&self.content[copied..]);
+        out
+    }
+}
+
+/// Where `path` ends up when `from` moves to `to`.
+fn moved(path: &Path, from: &Path, to: &Path) -> PathBuf {
+    match path.strip_prefix(from) {
+        Ok(rest) if rest.as_os_str().is_empty() => to.to_path_buf(),
+        Ok(rest) => to.join(rest),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// The page a wiki link needs once its target moves, if it changes: the new
+/// stem for links by name (or file name, when written with an extension),
+/// the new root-relative path for links by path. Links by alias still work.
+fn rewrite_wiki(link: &Link, root: &Path, from: &Path, to: &Path) -> Option<String> {
+    let target = PathBuf::from(link.path.as_ref()?);
+    let split = link.target.find('#').unwrap_or(link.target.len());
+    let (written, suffix) = link.target.split_at(split);
+    let new_target = moved(&target, from, to);
+    if written.trim().is_empty() || new_target == target {
+        return None;
+    }
+    let written_path = Path::new(written.trim());
+    let keep_ext = written_path.extension().is_some();
+    let page = if written.contains('/') {
+        let rel = if keep_ext {
+            link_graph::relative(&new_target, root)
+        } else {
+            link_graph::relative(&new_target.with_extension(""), root)
+        };
+        if written.trim_start().starts_with('/') {
+            format!("/{}", rel)
+        } else {
+            rel
+        }
+    } else {
+        let old_stem = target.file_stem().unwrap_or_default().to_string_lossy();
+        let written_stem = written_path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy();
+        if !written_stem.eq_ignore_ascii_case(&old_stem) {
+            return None;
+        }
+        let name = if keep_ext {
+            new_target.file_name()
+        } else {
+            new_target.file_stem()
+        };
+        name.unwrap_or_default().to_string_lossy().to_string()
+    };
+    (page != written).then(|| format!("{}{}", page, suffix))
+}
+
+/// The destination `link` (written in `source`) needs after the move, if it
+/// changes.
+fn rewrite_target(
+    link: &Link,
+    angled: bool,
+    source: &Path,
+    root: &Path,
+    from: &Path,
+    to: &Path,
+) -> Option<String> {
+    if link.kind.is_wiki() {
+        return rewrite_wiki(link, root, from, to);
+    }
+    let target = PathBuf::from(link.path.as_ref()?);
+    let split = link.target.find(['?', '#']).unwrap_or(link.target.len());
+    let (written, suffix) = link.target.split_at(split);
+    if written.is_empty() {
+        // `#fragment` stays within the file wherever it goes
+        return None;
+    }
+    let new_source = moved(source, from, to);
+    let new_target = moved(&target, from, to);
+    if new_source == source && new_target == target {
+        return None;
+    }
+
+    let mut path = match written.strip_prefix('/') {
+        Some(_) if new_target == target => return None,
+        Some(_) => format!("/{}", link_graph::relative(&new_target, root)),
+        None => link_graph::relative(&new_target, new_source.parent().unwrap_or(root)),
+    };
+    if path.is_empty() {
+        path.push('.');
+    }
+    if written.starts_with("./") && !path.starts_with("../") {
+        path = format!("./{}", path);
+    }
+    if written.ends_with('/') && !path.ends_with('/') {
+        path.push('/');
+    }
+    // spaces and unbalanced parentheses would end a bare destination
+    if !angled {
+        path = link_graph::escape_destination(&path);
+    } else if written.contains('%') {
+        path = path.replace('%', "%25").replace(' ', "%20");
+    }
+    (path != written).then(|| format!("{}{}", path, suffix))
+}
+
+/// Files under `root` whose links change when `from` moves to `to`.
+pub fn plan(root: &Path, config: &TreeConfig, from: &Path, to: &Path) -> Vec<Rewrite> {
+    // the index narrows the files to read; each is then reparsed from disk
+    let mut candidates: Vec<PathBuf> = link_graph::with_links(root, config, |files| {
+        files
+            .iter()
+            .filter(|(source, links)| {
+                source.starts_with(from)
+                    || links.iter().any(|l| {
+                        l.path
+                            .as_deref()
+                            .is_some_and(|p| Path::new(p).starts_with(from))
+                    })
+            })
+            .map(|(source, _)| source.clone())
+            .collect()
+    });
+    candidates.sort();
+
+    let mut rewrites = Vec::new();
+    for source in candidates {
+        let Ok(content) = std::fs::read_to_string(&source) else {
+            continue;
+        };
+        let edits: Vec<(Range<usize>, LinkEdit)> =
+            link_graph::parse_in_root(&content, &source, root, config)
+                .into_iter()
+                .filter_map(|link| {
+                    let angled = content[..link.span.start].ends_with('<');
+                    let to = rewrite_target(&link, angled, &source, root, from, to)?;
+                    let edit = LinkEdit {
+                        line: link.line,
+                        column: link.column,
+                        from: link.target,
+                        to,
+                    };
+                    Some((link.span, edit))
+                })
+                .collect();
+        if !edits.is_empty() {
+            rewrites.push(Rewrite {
+                new_path: moved(&source, from, to),
+                path: source,
+                content,
+                edits,
+            });
+        }
+    }
+    rewrites
+}
+
+/// What `plan` would change, for showing before the move.
+pub fn preview(root: &Path, rewrites: &[Rewrite], from: &Path, to: &Path) -> RenamePreview {
+    let files: Vec<FileEdits> = rewrites
+        .iter()
+        .map(|r| FileEdits {
+            path: r.path.to_string_lossy().to_string(),
+            rel_path: r
+                .path
+                .strip_prefix(root)
+                .unwrap_or(&r.path)
+                .to_string_lossy()
+                .to_string(),
+            stamp: r.stamp(),
+            edits: r.edits.iter().map(|(_, edit)| edit.clone()).collect(),
+        })
+        .collect();
+    RenamePreview {
+        from: from.to_string_lossy().to_string(),
+        to: to.to_string_lossy().to_string(),
+        total_edits: files.iter().map(|f| f.edits.len()).sum(),
+        files,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::link_graph::LinkKind;
+
+    const ROOT: &str = "/r";
+
+    /// New destination for each link in `content` (written in `source`)
+    /// when `from` moves to `to`.
+    fn rewrites(content: &str, source: &str, from: &str, to: &str) -> Vec<Option<String>> {
+        let (source, root) = (Path::new(source), Path::new(ROOT));
+        link_graph::parse(content, source, root)
+            .iter()
+            .map(|link| {
+                let angled = content[..link.span.start].ends_with('<');
+                rewrite_target(link, angled, source, root, Path::new(from), Path::new(to))
+            })
+            .collect()
+    }
+
+    fn wiki(target: &str, path: &str, from: &str, to: &str) -> Option<String> {
+        let link = Link {
+            kind: LinkKind::Wiki,
+            text: String::new(),
+            target: target.to_string(),
+            path: Some(path.to_string()),
+            fragment: None,
+            candidates: Vec::new(),
+            line: 1,
+            column: 1,
+            span: 0..0,
+        };
+        rewrite_wiki(&link, Path::new(ROOT), Path::new(from), Path::new(to))
+    }
+
+    fn some(s: &str) -> Option<String> {
+        Some(s.to_string())
+    }
+
+    #[test]
+    fn relative_link_follows_target() {
+        let got = rewrites("[b](docs/b.md)", "/r/a.md", "/r/docs/b.md", "/r/guide/b.md");
+        assert_eq!(got, vec![some("guide/b.md")]);
+    }
+
+    #[test]
+    fn keeps_dot_slash_and_fragment() {
+        let got = rewrites(
+            "[b](./docs/b.md#h)",
+            "/r/a.md",
+            "/r/docs/b.md",
+            "/r/guide/b.md",
+        );
+        assert_eq!(got, vec![some("./guide/b.md#h")]);
+    }
+
+    #[test]
+    fn root_absolute_stays_absolute() {
+        let got = rewrites(
+            "[b](/docs/b.md)",
+            "/r/x/a.md",
+            "/r/docs/b.md",
+            "/r/guide/b.md",
+        );
+        assert_eq!(got, vec![some("/guide/b.md")]);
+        // moving the source alone doesn't change a root-absolute link
+        let got = rewrites("[b](/docs/b.md)", "/r/x/a.md", "/r/x/a.md", "/r/y/a.md");
+        assert_eq!(got, vec![None]);
+    }
+
+    #[test]
+    fn escapes_spaces() {
+        let got = rewrites(
+            "[b](docs/my%20b.md)",
+            "/r/a.md",
+            "/r/docs/my b.md",
+            "/r/docs/your b.md",
+        );
+        assert_eq!(got, vec![some("docs/your%20b.md")]);
+        let got = rewrites(
+            "[b](docs/b.md)",
+            "/r/a.md",
+            "/r/docs/b.md",
+            "/r/docs/my b.md",
+        );
+        assert_eq!(got, vec![some("docs/my%20b.md")]);
+    }
+
+    #[test]
+    fn escapes_unbalanced_parentheses() {
+        let got = rewrites(
+            "[b](docs/b.md)",
+            "/r/a.md",
+            "/r/docs/b.md",
+            "/r/notes/a(1.md",
+        );
+        assert_eq!(got, vec![some("notes/a%281.md")]);
+        let got = rewrites(
+            "[b](docs/b.md)",
+            "/r/a.md",
+            "/r/docs/b.md",
+            "/r/notes/a(1).md",
+        );
+        assert_eq!(got, vec![some("notes/a(1).md")]);
+    }
+
+    #[test]
+    fn angle_brackets_keep_spaces() {
+        let got = rewrites(
+            "[b](<docs/my b.md>)",
+            "/r/a.md",
+            "/r/docs/my b.md",
+            "/r/docs/your b.md",
+        );
+        assert_eq!(got, vec![some("docs/your b.md")]);
+    }
+
+    #[test]
+    fn directory_move_adjusts_outgoing_links() {
+        let got = rewrites(
+            "[p](../img/p.png) [b](b.md)",
+            "/r/docs/a.md",
+            "/r/docs",
+            "/r/guide/docs",
+        );
+        assert_eq!(got, vec![some("../../img/p.png"), None]);
+    }
+
+    #[test]
+    fn self_links_are_left_alone() {
+        let got = rewrites(
+            "[t](#top) [a](a.md#top)",
+            "/r/a.md",
+            "/r/a.md",
+            "/r/sub/a.md",
+        );
+        assert_eq!(got, vec![None, None]);
+    }
+
+    #[test]
+    fn wiki_links_by_name_and_path() {
+        assert_eq!(
+            wiki("b", "/r/docs/b.md", "/r/docs/b.md", "/r/docs/c.md"),
+            some("c")
+        );
+        assert_eq!(
+            wiki("b.md", "/r/docs/b.md", "/r/docs/b.md", "/r/docs/c.md"),
+            some("c.md")
+        );
+        assert_eq!(
+            wiki("docs/b#h", "/r/docs/b.md", "/r/docs/b.md", "/r/guide/b.md"),
+            some("guide/b#h")
+        );
+        // a move that keeps the name keeps a link by name
+        assert_eq!(
+            wiki("b", "/r/docs/b.md", "/r/docs/b.md", "/r/guide/b.md"),
+            None
+        );
+        // links by alias still resolve
+        assert_eq!(
+            wiki("Bee", "/r/docs/b.md", "/r/docs/b.md", "/r/docs/c.md"),
+            None
+        );
+    }
+}
@@ -1,5 +1,26 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // `args_os`: GUI launches may pass file names that aren't valid UTF-8
+    if std::env::args_os()
+        .nth(1)
+        .is_some_and(|a| a == "check-links")
+    {
+        let args: Result<Vec<String>, _> = std::env::args_os()
+            .skip(2)
+            .map(|a| a.into_string())
+            .collect();
+        let code = match args {
+            Ok(args) => mdcat_lib::check_links_cli(&args),
+            Err(arg) => {
+                eprintln!(
+                    "mdcat: argument is not valid UTF-8: {}",
+                    arg.to_string_lossy()
+                );
+                2
+            }
+        };
+        std::process::exit(code);
+    }
     mdcat_lib::run();
 }
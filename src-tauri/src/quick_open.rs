@@ -0,0 +1,455 @@
+//! Fuzzy quick-open over every markdown file of the open roots: fzf-style
+//! scoring against relative paths and document titles, boosted for recently
+//! opened files. Candidates are prepared once per tree change (lowercased
+//! chars, titles from `file_meta`), so a query only runs the matcher; roots
+//! without a watcher index are re-prepared after `UNINDEXED_TTL`.
+
+use crate::config::TreeConfig;
+use crate::file_meta;
+use crate::file_tree::{self, ScanScope};
+use crate::tree_cache;
+use crate::tree_watcher;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+/// Results returned when the caller doesn't ask for a number.
+pub const DEFAULT_LIMIT: usize = 50;
+/// Recently opened files remembered (and boosted).
+const RECENT_MAX: usize = 50;
+/// Boost for the most recently opened file, fading to 0 along the list.
+const RECENT_BOOST: i32 = 48;
+const RECENT_FILENAME: &str = "recent.json";
+/// How long `record_open` waits before saving, so a burst of opens is one
+/// write.
+const RECENT_SAVE_DELAY: Duration = Duration::from_secs(2);
+/// How long candidates of a root without a watcher index are reused; nothing
+/// invalidates them.
+const UNINDEXED_TTL: Duration = Duration::from_secs(5);
+
+// Scoring after fzf's v2 algorithm: every matched char scores, gaps cost,
+// and chars at word boundaries or continuing a run earn bonuses.
+const SCORE_MATCH: i32 = 16;
+const SCORE_GAP_START: i32 = -3;
+const SCORE_GAP_EXTENSION: i32 = -1;
+const BONUS_BOUNDARY: i32 = SCORE_MATCH / 2;
+/// Start of text or after whitespace.
+const BONUS_BOUNDARY_WHITE: i32 = BONUS_BOUNDARY + 2;
+/// After a path separator.
+const BONUS_BOUNDARY_DELIMITER: i32 = BONUS_BOUNDARY + 1;
+const BONUS_CAMEL: i32 = BONUS_BOUNDARY + SCORE_GAP_EXTENSION;
+const BONUS_CONSECUTIVE: i32 = -(SCORE_GAP_START + SCORE_GAP_EXTENSION);
+const BONUS_FIRST_CHAR_MULTIPLIER: i32 = 2;
+
+/// A string prepared for fuzzy matching.
+pub struct Text {
+    chars: Vec<char>,
+    lower: Vec<char>,
+}
+
+impl Text {
+    pub fn new(s: &str) -> Text {
+        Text {
+            chars: s.chars().collect(),
+            lower: s.chars().flat_map(char::to_lowercase).collect(),
+        }
+    }
+}
+
+/// A markdown file prepared for matching by root-relative path and title.
+pub struct Candidate {
+    pub path: PathBuf,
+    pub rel: String,
+    pub rel_text: Text,
+    pub title: Option<String>,
+    pub title_text: Option<Text>,
+}
+
+/// Prepared candidates per watched root; dropped on every tree change.
+static CANDIDATES: Mutex<Option<HashMap<PathBuf, Arc<Vec<Candidate>>>>> = Mutex::new(None);
+/// When a root's candidates were prepared, and what was prepared.
+type TimedCandidates = (Instant, Arc<Vec<Candidate>>);
+
+/// Candidates of roots without a watcher index.
+static UNINDEXED: Mutex<Option<HashMap<PathBuf, TimedCandidates>>> = Mutex::new(None);
+/// Bumped by `invalidate`; candidates prepared across a bump aren't kept.
+static INVALIDATIONS: AtomicU64 = AtomicU64::new(0);
+/// Most recent first; `None` until loaded from disk.
+static RECENT: Mutex<Option<VecDeque<PathBuf>>> = Mutex::new(None);
+/// Set while a `record_open` save is scheduled.
+static RECENT_SAVE_PENDING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QuickOpenMatch {
+    pub path: String,
+    pub root: String,
+    /// Path relative to `root`, which is what `path_positions` index into.
+    pub rel_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub score: i32,
+    /// Char indices of matched chars in `rel_path`.
+    pub path_positions: Vec<usize>,
+    /// Char indices of matched chars in `title`.
+    pub title_positions: Vec<usize>,
+}
+
+/// Forget prepared candidates of `root` (files or titles changed, or it's
+/// no longer watched).
+pub fn invalidate(root: &Path) {
+    INVALIDATIONS.fetch_add(1, Ordering::SeqCst);
+    if let Ok(mut lock) = CANDIDATES.lock() {
+        if let Some(cache) = lock.as_mut() {
+            cache.remove(root);
+        }
+    }
+}
+
+fn prepare(root: &Path, files: Vec<PathBuf>) -> Vec<Candidate> {
+    files
+        .into_iter()
+        .map(|path| {
+            let rel = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .to_string();
+            let title = file_meta::get(&path).and_then(|m| m.title);
+            Candidate {
+                rel_text: Text::new(&rel),
+                title_text: title.as_deref().map(Text::new),
+                rel,
+                title,
+                path,
+            }
+        })
+        .collect()
+}
+
+/// Run `f` on the candidates of `root`, prepared without any lock held.
+/// Roots with a ready watcher index are kept until invalidated, others for
+/// `UNINDEXED_TTL`.
+pub fn with_candidates<T>(
+    root: &Path,
+    config: &TreeConfig,
+    f: impl FnOnce(&[Candidate]) -> T,
+) -> T {
+    let cached = CANDIDATES
+        .lock()
+        .ok()
+        .and_then(|l| l.as_ref()?.get(root).cloned());
+    if let Some(candidates) = cached {
+        return f(&candidates);
+    }
+    let invalidations = INVALIDATIONS.load(Ordering::SeqCst);
+    if let Some(files) = tree_watcher::cached_files(root, root) {
+        let candidates = Arc::new(prepare(root, files));
+        if INVALIDATIONS.load(Ordering::SeqCst) == invalidations {
+            if let Ok(mut lock) = CANDIDATES.lock() {
+                lock.get_or_insert_with(HashMap::new)
+                    .insert(root.to_path_buf(), Arc::clone(&candidates));
+            }
+        }
+        return f(&candidates);
+    }
+
+    let cached = UNINDEXED.lock().ok().and_then(|l| {
+        let (at, candidates) = l.as_ref()?.get(root)?;
+        (at.elapsed() < UNINDEXED_TTL).then(|| Arc::clone(candidates))
+    });
+    let candidates = cached.unwrap_or_else(|| {
+        let files = file_tree::scan_md_files(root, config, ScanScope::All);
+        let candidates = Arc::new(prepare(root, files));
+        if let Ok(mut lock) = UNINDEXED.lock() {
+            let unindexed = lock.get_or_insert_with(HashMap::new);
+            unindexed.retain(|_, (at, _)| at.elapsed() < UNINDEXED_TTL);
+            unindexed.insert(
+                root.to_path_buf(),
+                (Instant::now(), Arc::clone(&candidates)),
+            );
+        }
+        candidates
+    });
+    f(&candidates)
+}
+
+fn recent_file(app: &tauri::AppHandle) -> Option<PathBuf> {
+    Some(app.path().app_data_dir().ok()?.join(RECENT_FILENAME))
+}
+
+fn with_recent<T>(
+    app: &tauri::AppHandle,
+    f: impl FnOnce(&mut VecDeque<PathBuf>) -> T,
+) -> Option<T> {
+    let mut lock = RECENT.lock().ok()?;
+    let recent = lock.get_or_insert_with(|| {
+        recent_file(app)
+            .and_then(|p| std::fs::read(p).ok())
+            .and_then(|raw| serde_json::from_slice(&raw).ok())
+            .unwrap_or_default()
+    });
+    Some(f(recent))
+}
+
+/// Note that `path` was opened, for the recency boost. The list is saved
+/// `RECENT_SAVE_DELAY` later, along with any other opens by then.
+pub fn record_open(app: &tauri::AppHandle, path: &Path) {
+    let recorded = with_recent(app, |recent| {
+        recent.retain(|p| p != path);
+        recent.push_front(path.to_path_buf());
+        recent.truncate(RECENT_MAX);
+    });
+    if recorded.is_none() || RECENT_SAVE_PENDING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(RECENT_SAVE_DELAY);
+        // cleared before reading, so an open from now on schedules another save
+        RECENT_SAVE_PENDING.store(false, Ordering::SeqCst);
+        save_recent(&app);
+    });
+}
+
+fn save_recent(app: &tauri::AppHandle) {
+    let saved = with_recent(app, |recent| serde_json::to_vec(recent));
+    let (Some(Ok(contents)), Some(file)) = (saved, recent_file(app)) else {
+        return;
+    };
+    if let Err(e) = tree_cache::write_entry(&file, &contents) {
+        eprintln!(
+            "[mdcat] failed to save recent files {}: {}",
+            file.display(),
+            e
+        );
+    }
+}
+
+/// Bonus for matching `cur` given the char before it.
+fn bonus(prev: Option<char>, cur: char) -> i32 {
+    let Some(prev) = prev else {
+        return BONUS_BOUNDARY_WHITE;
+    };
+    if !cur.is_alphanumeric() {
+        return 0;
+    }
+    if prev.is_whitespace() {
+        BONUS_BOUNDARY_WHITE
+    } else if prev == '/' || prev == '\\' {
+        BONUS_BOUNDARY_DELIMITER
+    } else if !prev.is_alphanumeric() {
+        BONUS_BOUNDARY
+    } else if (prev.is_lowercase() && cur.is_uppercase())
+        || (!prev.is_numeric() && cur.is_numeric())
+    {
+        BONUS_CAMEL
+    } else {
+        0
+    }
+}
+
+/// Best-scoring alignment of `pattern` in `text` (smart case: sensitive only
+/// if `pattern` has an uppercase char) and the matched char indices.
+fn fuzzy_match(pattern: &[char], text: &Text) -> Option<(i32, Vec<usize>)> {
+    let case_sensitive = pattern.iter().any(|c| c.is_uppercase());
+    let hay = if case_sensitive {
+        &text.chars
+    } else {
+        &text.lower
+    };
+    // `to_lowercase` can expand a char; fall back to the original then
+    let hay = if hay.len() == text.chars.len() {
+        hay
+    } else {
+        &text.chars
+    };
+    let (m, n) = (pattern.len(), hay.len());
+    if m == 0 || m > n {
+        return None;
+    }
+
+    // cheap rejection, and a window from the first possible start to the
+    // last possible end
+    let mut first = None;
+    let mut i = 0;
+    for (j, c) in hay.iter().enumerate() {
+        if *c == pattern[i] {
+            first.get_or_insert(j);
+            i += 1;
+            if i == m {
+                break;
+            }
+        }
+    }
+    let first = first.filter(|_| i == m)?;
+    let last = hay.iter().rposition(|c| *c == pattern[m - 1])?;
+    let window = first..last + 1;
+    let width = window.len();
+
+    let bonuses: Vec<i32> = window
+        .clone()
+        .map(|j| bonus(j.checked_sub(1).map(|p| text.chars[p]), text.chars[j]))
+        .collect();
+
+    // score[i][j]: best with pattern[i] matched at window[j]; `from` remembers
+    // where pattern[i - 1] was matched, for recovering positions
+    const NONE: i32 = i32::MIN / 2;
+    let mut score = vec![NONE; m * width];
+    let mut from = vec![0usize; m * width];
+    for j in 0..width {
+        if hay[first + j] == pattern[0] {
+            score[j] = SCORE_MATCH + bonuses[j] * BONUS_FIRST_CHAR_MULTIPLIER;
+        }
+    }
+    for i in 1..m {
+        let (prev_row, row) = score.split_at_mut(i * width);
+        let prev_row = &prev_row[(i - 1) * width..];
+        let row = &mut row[..width];
+        // best `prev_row[k] + gap penalty` over k <= j - 2, and its k
+        let mut gapped = (NONE, 0);
+        for j in 1..width {
+            if j >= 2 {
+                let extended = gapped.0 + SCORE_GAP_EXTENSION;
+                let started = prev_row[j - 2] + SCORE_GAP_START;
+                gapped = if started >= extended {
+                    (started, j - 2)
+                } else {
+                    (extended, gapped.1)
+                };
+            }
+            if hay[first + j] != pattern[i] {
+                continue;
+            }
+            let consecutive = prev_row[j - 1] + SCORE_MATCH + bonuses[j].max(BONUS_CONSECUTIVE);
+            let gap = gapped.0 + SCORE_MATCH + bonuses[j];
+            let (best, k) = if consecutive >= gap {
+                (consecutive, j - 1)
+            } else {
+                (gap, gapped.1)
+            };
+            if best > NONE / 2 {
+                row[j] = best;
+                from[i * width + j] = k;
+            }
+        }
+    }
+
+    let last_row = &score[(m - 1) * width..];
+    let (mut j, best) = last_row
+        .iter()
+        .copied()
+        .enumerate()
+        .max_by_key(|(j, s)| (*s, std::cmp::Reverse(*j)))?;
+    if best <= NONE / 2 {
+        return None;
+    }
+    let mut positions = vec![0; m];
+    for i in (0..m).rev() {
+        positions[i] = first + j;
+        j = from[i * width + j];
+    }
+    Some((best, positions))
+}
+
+/// Whitespace-separated terms of a fuzzy query, for `match_terms`.
+pub fn query_terms(query: &str) -> Vec<Vec<char>> {
+    query
+        .split_whitespace()
+        .map(|t| t.chars().collect())
+        .collect()
+}
+
+/// Every term must match in `text`; scores add up, positions are merged.
+pub fn match_terms(terms: &[Vec<char>], text: &Text) -> Option<(i32, Vec<usize>)> {
+    let mut total = 0;
+    let mut positions = Vec::new();
+    for term in terms {
+        let (score, found) = fuzzy_match(term, text)?;
+        total += score;
+        positions.extend(found);
+    }
+    positions.sort_unstable();
+    positions.dedup();
+    Some((total, positions))
+}
+
+/// Score bonus per recently opened file, largest for the most recent.
+pub fn recency_boosts(app: &tauri::AppHandle) -> HashMap<PathBuf, i32> {
+    with_recent(app, |recent| {
+        recent
+            .iter()
+            .enumerate()
+            .map(|(rank, path)| {
+                let boost = RECENT_BOOST * (RECENT_MAX - rank) as i32 / RECENT_MAX as i32;
+                (path.clone(), boost)
+            })
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Up to `limit` files under `roots` matching `query` (whitespace-separated
+/// terms, all required), best first. An empty query lists recent files first.
+pub fn search(
+    app: &tauri::AppHandle,
+    roots: &[(PathBuf, TreeConfig)],
+    query: &str,
+    limit: usize,
+) -> Vec<QuickOpenMatch> {
+    let terms = query_terms(query);
+    let recency = recency_boosts(app);
+
+    let mut matches: Vec<QuickOpenMatch> = Vec::new();
+    for (root, config) in roots {
+        with_candidates(root, config, |candidates| {
+            // score everything, but only build results for the per-root top `limit`
+            let mut scored: Vec<(i32, &Candidate, Vec<usize>, Vec<usize>)> = Vec::new();
+            for c in candidates {
+                let boost = recency.get(&c.path).copied().unwrap_or(0);
+                if terms.is_empty() {
+                    scored.push((boost, c, Vec::new(), Vec::new()));
+                    continue;
+                }
+                let by_path = match_terms(&terms, &c.rel_text);
+                let by_title = c.title_text.as_ref().and_then(|t| match_terms(&terms, t));
+                let hit = match (by_path, by_title) {
+                    (None, None) => continue,
+                    (Some((s, p)), None) => (s, p, Vec::new()),
+                    (None, Some((s, t))) => (s, Vec::new(), t),
+                    (Some((ps, p)), Some((ts, _))) if ps >= ts => (ps, p, Vec::new()),
+                    (Some(_), Some((ts, t))) => (ts, Vec::new(), t),
+                };
+                scored.push((hit.0 + boost, c, hit.1, hit.2));
+            }
+            scored.sort_by(|a, b| rank(a.0, &a.1.rel, b.0, &b.1.rel));
+            scored.truncate(limit);
+            matches.extend(scored.into_iter().map(
+                |(score, c, path_positions, title_positions)| QuickOpenMatch {
+                    path: c.path.to_string_lossy().to_string(),
+                    root: root.to_string_lossy().to_string(),
+                    rel_path: c.rel.clone(),
+                    title: c.title.clone(),
+                    score,
+                    path_positions,
+                    title_positions,
+                },
+            ));
+        });
+    }
+
+    matches.sort_by(|a, b| rank(a.score, &a.rel_path, b.score, &b.rel_path));
+    matches.truncate(limit);
+    matches
+}
+
+/// Best score first; ties go to shorter paths, then alphabetical.
+fn rank(a_score: i32, a_rel: &str, b_score: i32, b_rel: &str) -> std::cmp::Ordering {
+    b_score
+        .cmp(&a_score)
+        .then(a_rel.len().cmp(&b_rel.len()))
+        .then_with(|| a_rel.cmp(b_rel))
+}
@@ -0,0 +1,674 @@
+//! Persistent full-text index per root for ranked search (BM25F over title,
+//! heading and body fields), phrase queries and prefix matching.
+//!
+//! An index is built the first time a root is searched, saved in the app
+//! cache dir and revalidated by file mtimes when loaded again; while loaded
+//! it follows the tree watcher's diffs. Roots without a watcher are instead
+//! revalidated by mtime on every search. Roots never searched cost nothing.
+
+use crate::config::TreeConfig;
+use crate::file_meta::{self, FenceState};
+use crate::file_tree::{self, ScanScope};
+use crate::tree_cache;
+use crate::tree_watcher::{self, TreeDiff};
+use crate::workspace_search;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Bump when the index layout or tokenization changes; older files are rebuilt.
+const INDEX_VERSION: u32 = 2;
+/// Hits returned when the caller doesn't ask for a number.
+pub const DEFAULT_LIMIT: usize = 50;
+/// Most terms a single prefix expands to.
+const MAX_EXPANSIONS: usize = 64;
+/// Watcher updates are saved at most this often; a load revalidates anyway.
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+const TITLE: usize = 0;
+const HEADING: usize = 1;
+const BODY: usize = 2;
+const FIELD_COUNT: usize = 3;
+/// Per-field weights: a hit in the frontmatter title or a heading counts more.
+const BOOSTS: [f32; FIELD_COUNT] = [3.0, 2.0, 1.0];
+const K1: f32 = 1.2;
+const B: f32 = 0.75;
+
+static INDEXES: Mutex<Option<HashMap<PathBuf, Index>>> = Mutex::new(None);
+
+/// Indexes of roots without a ready watcher index. No diffs reach them, so
+/// each search takes one out and brings it in line with the disk first.
+static UNWATCHED: Mutex<Option<HashMap<PathBuf, Index>>> = Mutex::new(None);
+
+/// Diffs that found their root's index not loaded. An index built while one
+/// arrived may predate it, so it serves that call but isn't kept.
+static MISSED_DIFFS: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Doc {
+    /// Relative to the root.
+    path: PathBuf,
+    mtime: u64,
+    title: Option<String>,
+    /// Token count per field.
+    lengths: [u32; FIELD_COUNT],
+    /// Distinct terms, so removal needn't walk the whole term map.
+    terms: Vec<String>,
+}
+
+/// Token positions of one term in one doc, per field.
+type Postings = [Vec<u32>; FIELD_COUNT];
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Index {
+    version: u32,
+    /// Canonical root; guards against hash collisions.
+    root: PathBuf,
+    docs: BTreeMap<u32, Doc>,
+    next_id: u32,
+    terms: BTreeMap<String, BTreeMap<u32, Postings>>,
+    #[serde(skip)]
+    ids: HashMap<PathBuf, u32>,
+    #[serde(skip)]
+    dirty: bool,
+    #[serde(skip)]
+    saved_at: Option<Instant>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexHit {
+    pub path: String,
+    /// Frontmatter `title`, else the first H1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub score: f32,
+    /// 1-based line of the snippet; 0 when only the title matched.
+    pub line: usize,
+    pub snippet: String,
+    /// `[start, end)` char ranges of matched words within `snippet`.
+    pub highlights: Vec<(usize, usize)>,
+}
+
+/// Lowercased alphanumeric runs of `text` with their byte ranges.
+fn tokens(text: &str) -> impl Iterator<Item = (String, usize, usize)> + '_ {
+    let mut chars = text.char_indices().peekable();
+    std::iter::from_fn(move || {
+        while chars.peek().is_some_and(|(_, c)| !c.is_alphanumeric()) {
+            chars.next();
+        }
+        let (start, _) = *chars.peek()?;
+        let mut end = start;
+        while let Some((i, c)) = chars.peek().copied().filter(|(_, c)| c.is_alphanumeric()) {
+            end = i + c.len_utf8();
+            chars.next();
+        }
+        Some((text[start..end].to_lowercase(), start, end))
+    })
+}
+
+/// A file's terms, read and tokenized apart from any index.
+struct Analyzed {
+    mtime: u64,
+    title: Option<String>,
+    lengths: [u32; FIELD_COUNT],
+    fields: HashMap<String, Postings>,
+}
+
+/// Read and tokenize `path`; `None` if it can't be read.
+fn analyze(path: &Path) -> Option<Analyzed> {
+    let mtime = file_tree::mtime_ms(path)?;
+    let content = std::fs::read_to_string(path).ok()?;
+
+    let mut fields: HashMap<String, Postings> = HashMap::new();
+    let mut lengths = [0u32; FIELD_COUNT];
+    let mut add = |field: usize, text: &str| {
+        for (term, _, _) in tokens(text) {
+            fields.entry(term).or_default()[field].push(lengths[field]);
+            lengths[field] += 1;
+        }
+    };
+
+    let (fm, body, _) = file_meta::split_frontmatter(&content);
+    let mut title = fm.and_then(|fm| file_meta::parse_frontmatter(fm).remove("title"));
+    if let Some(t) = &title {
+        add(TITLE, t);
+    }
+    let mut fences = FenceState::default();
+    for line in body.lines() {
+        let heading = if fences.is_code(line) {
+            None
+        } else {
+            file_meta::atx_heading(line)
+        };
+        match heading {
+            Some((level, text)) => {
+                if level == 1 && title.is_none() {
+                    title = Some(text.to_string());
+                }
+                add(HEADING, text);
+            }
+            None => add(BODY, line),
+        }
+    }
+    Some(Analyzed {
+        mtime,
+        title,
+        lengths,
+        fields,
+    })
+}
+
+impl Index {
+    fn new(root: PathBuf) -> Index {
+        Index {
+            version: INDEX_VERSION,
+            root,
+            ..Index::default()
+        }
+    }
+
+    fn remove(&mut self, rel: &Path) {
+        let Some(id) = self.ids.remove(rel) else {
+            return;
+        };
+        let Some(doc) = self.docs.remove(&id) else {
+            return;
+        };
+        for term in &doc.terms {
+            if let Some(postings) = self.terms.get_mut(term) {
+                postings.remove(&id);
+                if postings.is_empty() {
+                    self.terms.remove(term);
+                }
+            }
+        }
+        self.dirty = true;
+    }
+
+    /// (Re)index `path`; unreadable files are just dropped.
+    fn update(&mut self, root: &Path, path: &Path) {
+        let rel = path.strip_prefix(root).unwrap_or(path).to_path_buf();
+        self.insert(rel, analyze(path));
+    }
+
+    /// Replace the doc at `rel` with `analyzed`, or just drop it.
+    fn insert(&mut self, rel: PathBuf, analyzed: Option<Analyzed>) {
+        self.remove(&rel);
+        let Some(Analyzed {
+            mtime,
+            title,
+            lengths,
+            fields,
+        }) = analyzed
+        else {
+            return;
+        };
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let mut terms = Vec::with_capacity(fields.len());
+        for (term, postings) in fields {
+            self.terms
+                .entry(term.clone())
+                .or_default()
+                .insert(id, postings);
+            terms.push(term);
+        }
+        self.docs.insert(
+            id,
+            Doc {
+                path: rel.clone(),
+                mtime,
+                title,
+                lengths,
+                terms,
+            },
+        );
+        self.ids.insert(rel, id);
+        self.dirty = true;
+    }
+
+    /// Bring the index in line with `files`: index new and changed ones,
+    /// drop those that are gone.
+    fn reconcile(&mut self, root: &Path, files: &[PathBuf]) {
+        let current: HashSet<PathBuf> = files
+            .iter()
+            .map(|p| p.strip_prefix(root).unwrap_or(p).to_path_buf())
+            .collect();
+        let gone: Vec<PathBuf> = self
+            .ids
+            .keys()
+            .filter(|rel| !current.contains(*rel))
+            .cloned()
+            .collect();
+        for rel in gone {
+            self.remove(&rel);
+        }
+        for path in files {
+            let rel = path.strip_prefix(root).unwrap_or(path);
+            let indexed = self.ids.get(rel).and_then(|id| self.docs.get(id));
+            if indexed.map(|d| d.mtime) != file_tree::mtime_ms(path) {
+                self.update(root, path);
+            }
+        }
+    }
+
+    /// Terms a clause word stands for: itself, or every indexed term it prefixes.
+    fn expand(&self, word: &str, prefix: bool) -> Vec<&str> {
+        if !prefix {
+            return self
+                .terms
+                .get_key_value(word)
+                .map(|(t, _)| vec![t.as_str()])
+                .unwrap_or_default();
+        }
+        self.terms
+            .range::<str, _>((Bound::Included(word), Bound::Unbounded))
+            .take_while(|(t, _)| t.starts_with(word))
+            .take(MAX_EXPANSIONS)
+            .map(|(t, _)| t.as_str())
+            .collect()
+    }
+
+    /// Per-doc, per-field occurrence counts of `clause`.
+    fn clause_tf(&self, clause: &Clause) -> HashMap<u32, [f32; FIELD_COUNT]> {
+        let mut tf: HashMap<u32, [f32; FIELD_COUNT]> = HashMap::new();
+        match clause {
+            Clause::Word { text, prefix } => {
+                for term in self.expand(text, *prefix) {
+                    for (id, postings) in &self.terms[term] {
+                        let counts = tf.entry(*id).or_default();
+                        for f in 0..FIELD_COUNT {
+                            counts[f] += postings[f].len() as f32;
+                        }
+                    }
+                }
+            }
+            Clause::Phrase(words) => {
+                let lists: Option<Vec<&BTreeMap<u32, Postings>>> =
+                    words.iter().map(|w| self.terms.get(w)).collect();
+                let Some(lists) = lists else {
+                    return tf;
+                };
+                for (id, first) in lists[0] {
+                    let rest: Option<Vec<&Postings>> =
+                        lists[1..].iter().map(|l| l.get(id)).collect();
+                    let Some(rest) = rest else {
+                        continue;
+                    };
+                    let mut counts = [0.0; FIELD_COUNT];
+                    for f in 0..FIELD_COUNT {
+                        counts[f] = first[f]
+                            .iter()
+                            .filter(|&&pos| {
+                                rest.iter()
+                                    .enumerate()
+                                    .all(|(i, p)| p[f].binary_search(&(pos + i as u32 + 1)).is_ok())
+                            })
+                            .count() as f32;
+                    }
+                    if counts.iter().any(|c| *c > 0.0) {
+                        tf.insert(*id, counts);
+                    }
+                }
+            }
+        }
+        tf
+    }
+
+    /// BM25F scores of docs matching every clause, best first.
+    fn rank(&self, clauses: &[Clause]) -> Vec<(u32, f32)> {
+        let n = self.docs.len() as f32;
+        if n == 0.0 || clauses.is_empty() {
+            return Vec::new();
+        }
+        let mut avg = [0.0f32; FIELD_COUNT];
+        for doc in self.docs.values() {
+            for (total, len) in avg.iter_mut().zip(doc.lengths) {
+                *total += len as f32;
+            }
+        }
+        for a in &mut avg {
+            *a = (*a / n).max(1.0);
+        }
+
+        let mut scores: Option<HashMap<u32, f32>> = None;
+        for clause in clauses {
+            let tf = self.clause_tf(clause);
+            let df = tf.len() as f32;
+            let idf = (1.0 + (n - df + 0.5) / (df + 0.5)).ln();
+            let mut next = HashMap::new();
+            for (id, counts) in tf {
+                let Some(prev) = scores.as_ref().map_or(Some(0.0), |s| s.get(&id).copied()) else {
+                    continue;
+                };
+                let doc = &self.docs[&id];
+                let weighted: f32 = (0..FIELD_COUNT)
+                    .map(|f| {
+                        let norm = 1.0 - B + B * doc.lengths[f] as f32 / avg[f];
+                        BOOSTS[f] * counts[f] / norm
+                    })
+                    .sum();
+                next.insert(id, prev + idf * weighted * (K1 + 1.0) / (weighted + K1));
+            }
+            scores = Some(next);
+        }
+
+        let mut ranked: Vec<(u32, f32)> = scores.unwrap_or_default().into_iter().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked
+    }
+}
+
+#[derive(Debug)]
+enum Clause {
+    /// One term; `prefix` also matches longer terms starting with it.
+    Word { text: String, prefix: bool },
+    /// Consecutive terms in one field.
+    Phrase(Vec<String>),
+}
+
+/// `"quoted words"` are phrases, `word*` is a prefix, and so is the last word
+/// while it's still being typed (no trailing space). Words that split into
+/// several terms (`foo-bar`) must appear as a phrase.
+fn parse_query(query: &str) -> Vec<Clause> {
+    let mut clauses = Vec::new();
+    let mut rest = query;
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+        let (raw, quoted, tail) = match rest.strip_prefix('"') {
+            Some(inner) => match inner.find('"') {
+                Some(end) => (&inner[..end], true, &inner[end + 1..]),
+                None => (inner, true, ""),
+            },
+            None => {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                (&rest[..end], false, &rest[end..])
+            }
+        };
+        rest = tail;
+        let typing = !quoted && tail.is_empty() && !query.ends_with(char::is_whitespace);
+        let prefix = !quoted && (raw.ends_with('*') || typing);
+        let mut words: Vec<String> = tokens(raw).map(|(t, _, _)| t).collect();
+        match words.len() {
+            0 => {}
+            1 => clauses.push(Clause::Word {
+                text: words.remove(0),
+                prefix,
+            }),
+            _ => clauses.push(Clause::Phrase(words)),
+        }
+    }
+    clauses
+}
+
+fn load(app: &tauri::AppHandle, canonical: &Path) -> Option<Index> {
+    let raw = std::fs::read(tree_cache::cache_file(app, "search", canonical)?).ok()?;
+    let mut index: Index = serde_json::from_slice(&raw).ok()?;
+    if index.version != INDEX_VERSION || index.root != canonical {
+        return None;
+    }
+    index.ids = index
+        .docs
+        .iter()
+        .map(|(id, doc)| (doc.path.clone(), *id))
+        .collect();
+    index.saved_at = Some(Instant::now());
+    Some(index)
+}
+
+/// Write `index` if it changed. Failures are logged: the index can always be rebuilt.
+fn save(app: &tauri::AppHandle, index: &mut Index) {
+    if !index.dirty {
+        return;
+    }
+    let Some(path) = tree_cache::cache_file(app, "search", &index.root) else {
+        return;
+    };
+    let result = serde_json::to_vec(&*index)
+        .map_err(std::io::Error::from)
+        .and_then(|bytes| tree_cache::write_entry(&path, &bytes));
+    match result {
+        Ok(()) => {
+            index.dirty = false;
+            index.saved_at = Some(Instant::now());
+        }
+        Err(e) => eprintln!(
+            "[mdcat] failed to write search index {}: {}",
+            path.display(),
+            e
+        ),
+    }
+}
+
+/// Run `f` on the index of `root`, loading (or building) it first. The
+/// build runs without the lock, so other roots' queries and diffs go on.
+fn with_index<T>(
+    app: &tauri::AppHandle,
+    root: &Path,
+    config: &TreeConfig,
+    f: impl FnOnce(&Index) -> T,
+) -> Result<T, String> {
+    let unavailable = |_| "Search index unavailable".to_string();
+    let missed = MISSED_DIFFS.load(Ordering::SeqCst);
+    if let Some(index) = INDEXES
+        .lock()
+        .map_err(unavailable)?
+        .as_ref()
+        .and_then(|i| i.get(root))
+    {
+        return Ok(f(index));
+    }
+
+    let canonical = root
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", root.display(), e))?;
+    let started = Instant::now();
+    // also hands an unwatched root's index over once its watcher is ready
+    let kept = UNWATCHED
+        .lock()
+        .ok()
+        .and_then(|mut l| l.as_mut()?.remove(root));
+    let reused = kept.is_some();
+    let mut index = kept
+        .or_else(|| load(app, &canonical))
+        .unwrap_or_else(|| Index::new(canonical));
+    let watched = tree_watcher::cached_files(root, root);
+    let files = match &watched {
+        Some(files) => files.clone(),
+        None => file_tree::scan_md_files(root, config, ScanScope::All),
+    };
+    index.reconcile(root, &files);
+    save(app, &mut index);
+    if !reused {
+        eprintln!(
+            "[mdcat] search index ready: {} ({} docs, {} terms) in {}ms",
+            root.display(),
+            index.docs.len(),
+            index.terms.len(),
+            started.elapsed().as_millis()
+        );
+    }
+
+    if watched.is_none() {
+        let result = f(&index);
+        let mut lock = UNWATCHED.lock().map_err(unavailable)?;
+        lock.get_or_insert_with(HashMap::new)
+            .insert(root.to_path_buf(), index);
+        return Ok(result);
+    }
+    let mut lock = INDEXES.lock().map_err(unavailable)?;
+    let indexes = lock.get_or_insert_with(HashMap::new);
+    if MISSED_DIFFS.load(Ordering::SeqCst) != missed && !indexes.contains_key(root) {
+        return Ok(f(&index));
+    }
+    Ok(f(indexes.entry(root.to_path_buf()).or_insert(index)))
+}
+
+/// Apply a `tree-changed` diff to the root's index, if one is loaded.
+/// Changed files are read and tokenized before the lock is taken again.
+pub fn apply_diff(app: &tauri::AppHandle, diff: &TreeDiff) {
+    let root = PathBuf::from(&diff.root);
+    let rel = |p: &str| {
+        let p = Path::new(p);
+        p.strip_prefix(&root).unwrap_or(p).to_path_buf()
+    };
+    let modified: Vec<&String> = {
+        let Ok(lock) = INDEXES.lock() else {
+            return;
+        };
+        let Some(index) = lock.as_ref().and_then(|i| i.get(&root)) else {
+            MISSED_DIFFS.fetch_add(1, Ordering::SeqCst);
+            return;
+        };
+        // `modified` also carries non-markdown files such as `mkdocs.yml`
+        diff.modified
+            .iter()
+            .filter(|p| index.ids.contains_key(&rel(p)))
+            .collect()
+    };
+    let analyzed: Vec<(PathBuf, Option<Analyzed>)> = diff
+        .added
+        .iter()
+        .chain(diff.renamed.iter().map(|r| &r.to))
+        .chain(modified)
+        .map(|p| (rel(p), analyze(Path::new(p))))
+        .collect();
+
+    let Ok(mut lock) = INDEXES.lock() else {
+        return;
+    };
+    let Some(index) = lock.as_mut().and_then(|i| i.get_mut(&root)) else {
+        MISSED_DIFFS.fetch_add(1, Ordering::SeqCst);
+        return;
+    };
+    for path in &diff.removed {
+        index.remove(&rel(path));
+    }
+    for renamed in &diff.renamed {
+        index.remove(&rel(&renamed.from));
+    }
+    for (rel, analyzed) in analyzed {
+        index.insert(rel, analyzed);
+    }
+    if index.saved_at.is_none_or(|t| t.elapsed() >= SAVE_INTERVAL) {
+        save(app, index);
+    }
+}
+
+/// Save and drop loaded indexes of roots not in `keep` (no longer watched,
+/// so they'd go stale in memory; the next load revalidates from disk).
+pub fn retain(app: &tauri::AppHandle, keep: &[PathBuf]) {
+    for map in [&INDEXES, &UNWATCHED] {
+        if let Ok(mut lock) = map.lock() {
+            if let Some(indexes) = lock.as_mut() {
+                indexes.retain(|root, index| {
+                    if keep.contains(root) {
+                        return true;
+                    }
+                    save(app, index);
+                    false
+                });
+            }
+        }
+    }
+}
+
+/// Top `limit` hits for `query` across `roots` (each with its tree config).
+pub fn search(
+    app: &tauri::AppHandle,
+    roots: &[(PathBuf, TreeConfig)],
+    query: &str,
+    limit: usize,
+) -> Result<Vec<IndexHit>, String> {
+    let clauses = parse_query(query);
+    if clauses.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut ranked: Vec<(f32, PathBuf, Option<String>, HashSet<String>)> = Vec::new();
+    for (root, config) in roots {
+        with_index(app, root, config, |index| {
+            // the concrete terms to highlight (prefixes expanded)
+            let mut words: HashSet<String> = HashSet::new();
+            for clause in &clauses {
+                match clause {
+                    Clause::Word { text, prefix } => {
+                        words.extend(index.expand(text, *prefix).into_iter().map(String::from))
+                    }
+                    Clause::Phrase(terms) => words.extend(terms.iter().cloned()),
+                }
+            }
+            for (id, score) in index.rank(&clauses).into_iter().take(limit) {
+                let doc = &index.docs[&id];
+                ranked.push((
+                    score,
+                    root.join(&doc.path),
+                    doc.title.clone(),
+                    words.clone(),
+                ));
+            }
+        })?;
+    }
+    ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+    ranked.truncate(limit);
+
+    Ok(ranked
+        .into_iter()
+        .map(|(score, path, title, words)| {
+            let (line, snippet, highlights) = best_snippet(&path, &words);
+            IndexHit {
+                path: path.to_string_lossy().to_string(),
+                title,
+                score,
+                line,
+                snippet,
+                highlights,
+            }
+        })
+        .collect())
+}
+
+/// The body line with the most distinct query words, cut to size, with
+/// every query word in it highlighted.
+fn best_snippet(path: &Path, words: &HashSet<String>) -> (usize, String, Vec<(usize, usize)>) {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return (0, String::new(), Vec::new());
+    };
+    let (_, body, body_start) = file_meta::split_frontmatter(&content);
+    let best = body
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let hits: Vec<(String, usize, usize)> =
+                tokens(line).filter(|(t, _, _)| words.contains(t)).collect();
+            let distinct = hits.iter().map(|(t, _, _)| t).collect::<HashSet<_>>().len();
+            (distinct, i, line, hits)
+        })
+        .filter(|(distinct, ..)| *distinct > 0)
+        .max_by_key(|(distinct, i, ..)| (*distinct, std::cmp::Reverse(*i)));
+    let Some((_, index, line, hits)) = best else {
+        return (0, String::new(), Vec::new());
+    };
+
+    let char_at = |byte: usize| line[..byte].chars().count();
+    let first = char_at(hits[0].1);
+    let first_len = char_at(hits[0].2) - first;
+    let (snippet, offset) = workspace_search::snippet(line.trim_end(), first, first_len);
+    let shift = first - offset;
+    let visible = snippet.chars().count();
+    let highlights = hits
+        .iter()
+        .map(|(_, start, end)| (char_at(*start), char_at(*end)))
+        .filter(|(start, end)| *start >= shift && end - shift <= visible)
+        .map(|(start, end)| (start - shift, end - shift))
+        .collect();
+    (body_start + index + 1, snippet, highlights)
+}
@@ -0,0 +1,174 @@
+//! On-disk cache of the last full scan per root, so a cold start can paint
+//! the tree before walking anything. Entries live in the app cache dir, keyed
+//! by canonical root, and are validated by the mtimes of every walked
+//! directory and rule file: creating, deleting or renaming an entry bumps
+//! its parent directory's mtime.
+
+use crate::config::{SortKey, SortOrder, TreeConfig};
+use crate::file_tree;
+use crate::tree_scan;
+use crate::tree_watcher::{self, TreeDiff};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::Manager;
+
+/// Bump when the file layout or the metadata derived from files (titles,
+/// counts) changes; older entries are ignored.
+const CACHE_VERSION: u32 = 2;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CachedScan {
+    version: u32,
+    /// Canonical root; guards against hash collisions.
+    root: PathBuf,
+    /// The scan rules it ran with (see `config_key`); any change invalidates it.
+    config: String,
+    /// Markdown files, relative to the root.
+    files: Vec<PathBuf>,
+    /// Walked directories and rule files (relative) with their mtimes.
+    stamps: Vec<(PathBuf, u64)>,
+}
+
+impl CachedScan {
+    /// Cached files as absolute paths under `root`.
+    pub fn files(&self, root: &Path) -> Vec<PathBuf> {
+        self.files.iter().map(|rel| root.join(rel)).collect()
+    }
+
+    /// Whether every recorded directory and rule file is unchanged on disk.
+    pub fn is_fresh(&self, root: &Path) -> bool {
+        self.stamps
+            .iter()
+            .all(|(rel, mtime)| file_tree::mtime_ms(&root.join(rel)) == Some(*mtime))
+    }
+}
+
+/// FNV-1a of the path's bytes. Unlike `DefaultHasher`, it's the same on
+/// every Rust release, so cache files survive toolchain updates.
+fn path_hash(path: &Path) -> u64 {
+    path.as_os_str()
+        .as_encoded_bytes()
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+/// Per-root cache file under `app_cache_dir/<kind>/`, named by a hash of the root.
+pub fn cache_file(app: &tauri::AppHandle, kind: &str, canonical_root: &Path) -> Option<PathBuf> {
+    let dir = app.path().app_cache_dir().ok()?.join(kind);
+    Some(dir.join(format!("{:016x}.json", path_hash(canonical_root))))
+}
+
+/// Write a cache or settings file through a temp sibling, so a crash never
+/// leaves a truncated entry. Each write gets its own temp name; threads
+/// saving the same entry don't clobber each other's.
+pub fn write_entry(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    static NEXT_TMP: AtomicU64 = AtomicU64::new(0);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = path.with_file_name(format!(
+        "{}.{}.{}",
+        name,
+        std::process::id(),
+        NEXT_TMP.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::write(&tmp, bytes)?;
+    std::fs::rename(&tmp, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&tmp);
+    })
+}
+
+/// The parts of `config` that decide which files a scan finds; ordering
+/// and the node cap only matter when the tree is assembled.
+fn config_key(config: &TreeConfig) -> String {
+    let scan_rules = TreeConfig {
+        sort: SortOrder::DirsFirst,
+        sort_by: SortKey::Natural,
+        max_nodes: 0,
+        ..config.clone()
+    };
+    serde_json::to_string(&scan_rules).unwrap_or_default()
+}
+
+/// Last saved scan of `root`, if it was made with the same `config`.
+/// The entry may be stale; check `is_fresh` or hand it to `reconcile`.
+pub fn load(app: &tauri::AppHandle, root: &Path, config: &TreeConfig) -> Option<CachedScan> {
+    let canonical = root.canonicalize().ok()?;
+    let raw = std::fs::read(cache_file(app, "trees", &canonical)?).ok()?;
+    let cached: CachedScan = serde_json::from_slice(&raw).ok()?;
+    (cached.version == CACHE_VERSION
+        && cached.root == canonical
+        && cached.config == config_key(config))
+    .then_some(cached)
+}
+
+/// Save a completed full scan of `root`. Failures are logged, not returned:
+/// the cache only ever speeds things up.
+pub fn store(
+    app: &tauri::AppHandle,
+    root: &Path,
+    config: &TreeConfig,
+    files: &[PathBuf],
+    stamps: Vec<(PathBuf, u64)>,
+) {
+    let Ok(canonical) = root.canonicalize() else {
+        return;
+    };
+    let Some(path) = cache_file(app, "trees", &canonical) else {
+        return;
+    };
+    let relative = |p: &Path| p.strip_prefix(root).unwrap_or(p).to_path_buf();
+    let cached = CachedScan {
+        version: CACHE_VERSION,
+        root: canonical,
+        config: config_key(config),
+        files: files.iter().map(|p| relative(p)).collect(),
+        stamps: stamps
+            .into_iter()
+            .map(|(p, mtime)| (relative(&p), mtime))
+            .collect(),
+    };
+
+    let result = serde_json::to_vec(&cached)
+        .map_err(std::io::Error::from)
+        .and_then(|bytes| write_entry(&path, &bytes));
+    if let Err(e) = result {
+        eprintln!(
+            "[mdcat] failed to write tree cache {}: {}",
+            path.display(),
+            e
+        );
+    }
+}
+
+/// Validate `cached` against the disk on a background thread. If anything
+/// moved, rescan `root` (which refreshes the cache) and publish what changed
+/// relative to the cached files as `tree-changed`, through the watcher so
+/// its files and the indexes follow.
+pub fn reconcile(root: PathBuf, cached: CachedScan, config: TreeConfig, app: tauri::AppHandle) {
+    std::thread::spawn(move || {
+        if cached.is_fresh(&root) {
+            return;
+        }
+        let before: BTreeSet<PathBuf> = cached.files(&root).into_iter().collect();
+        let Some(after) = tree_scan::scan(&root, &config, &app) else {
+            return;
+        };
+        let after: BTreeSet<PathBuf> = after.into_iter().collect();
+        let diff = TreeDiff::between(&root, &before, &after);
+        if !diff.is_empty() {
+            eprintln!(
+                "[mdcat] tree cache reconciled: +{} -{} ~{}",
+                diff.added.len(),
+                diff.removed.len(),
+                diff.renamed.len()
+            );
+        }
+        tree_watcher::publish_scan(&app, &root, after, diff);
+    });
+}
@@ -0,0 +1,183 @@
+//! Background, cancellable full scans of a root.
+//! Scans run on the parallel walker, stream `tree-progress` events with
+//! partial results, and are cancelled when the current root switches away.
+//! A scan asked for while one of the same root runs waits for that one's
+//! files instead of walking again, so the tree and the watcher's index share
+//! a cold start. Completed scans are saved to the on-disk `tree_cache`.
+
+use crate::config::TreeConfig;
+use crate::file_tree::{self, ScanScope};
+use crate::tree_cache;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+
+/// Minimum gap between two `tree-progress` events for one scan.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+struct ActiveScan {
+    root: PathBuf,
+    cancelled: Arc<AtomicBool>,
+    outcome: Arc<Outcome>,
+}
+
+/// Where a running scan leaves its files for callers waiting on it.
+#[derive(Default)]
+struct Outcome {
+    /// Set when the scan ends: its files, or `None` if it was cancelled.
+    result: Mutex<Option<Option<Vec<PathBuf>>>>,
+    ready: Condvar,
+}
+
+impl Outcome {
+    fn finish(&self, files: Option<Vec<PathBuf>>) {
+        if let Ok(mut result) = self.result.lock() {
+            *result = Some(files);
+        }
+        self.ready.notify_all();
+    }
+
+    fn wait(&self) -> Option<Vec<PathBuf>> {
+        let mut result = self.result.lock().ok()?;
+        loop {
+            if let Some(files) = &*result {
+                return files.clone();
+            }
+            result = self.ready.wait(result).ok()?;
+        }
+    }
+}
+
+static ACTIVE: Mutex<Vec<ActiveScan>> = Mutex::new(Vec::new());
+
+/// Payload of the `tree-progress` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanProgress {
+    pub root: String,
+    pub files_found: usize,
+    /// Files found since the previous event for this scan.
+    pub partial: Vec<String>,
+    pub done: bool,
+    pub cancelled: bool,
+}
+
+/// Cancel in-flight scans of any root not in `keep`.
+pub fn cancel_other_roots(keep: &[PathBuf]) {
+    if let Ok(active) = ACTIVE.lock() {
+        for scan in active.iter().filter(|s| !keep.contains(&s.root)) {
+            scan.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Cancel in-flight scans of `root`.
+pub fn cancel(root: &Path) {
+    if let Ok(active) = ACTIVE.lock() {
+        for scan in active.iter().filter(|s| s.root == root) {
+            scan.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Scan all of `root` on the parallel walker, emitting `tree-progress` on `app`,
+/// or wait for the scan of `root` already running. Returns `None` if the
+/// scan was cancelled before finishing.
+pub fn scan(root: &Path, config: &TreeConfig, app: &tauri::AppHandle) -> Option<Vec<PathBuf>> {
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let outcome = Arc::new(Outcome::default());
+    let running = ACTIVE.lock().ok().and_then(|mut active| {
+        if let Some(scan) = active
+            .iter()
+            .find(|s| s.root == root && !s.cancelled.load(Ordering::Relaxed))
+        {
+            return Some(Arc::clone(&scan.outcome));
+        }
+        active.push(ActiveScan {
+            root: root.to_path_buf(),
+            cancelled: Arc::clone(&cancelled),
+            outcome: Arc::clone(&outcome),
+        });
+        None
+    });
+    if let Some(running) = running {
+        return running.wait();
+    }
+
+    let root_str = root.to_string_lossy().to_string();
+    let found: Mutex<(Vec<PathBuf>, usize, Instant)> = Mutex::new((Vec::new(), 0, Instant::now()));
+    let stamps: Mutex<Vec<(PathBuf, u64)>> = Mutex::new(Vec::new());
+
+    file_tree::scan_md_files_parallel(
+        root,
+        config,
+        ScanScope::All,
+        |path| {
+            if cancelled.load(Ordering::Relaxed) {
+                return false;
+            }
+            let Ok(mut guard) = found.lock() else {
+                return false;
+            };
+            let (files, sent, last_emit) = &mut *guard;
+            files.push(path.to_path_buf());
+            if last_emit.elapsed() >= PROGRESS_INTERVAL {
+                let partial = files[*sent..]
+                    .iter()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .collect();
+                *sent = files.len();
+                *last_emit = Instant::now();
+                let _ = app.emit(
+                    "tree-progress",
+                    ScanProgress {
+                        root: root_str.clone(),
+                        files_found: files.len(),
+                        partial,
+                        done: false,
+                        cancelled: false,
+                    },
+                );
+            }
+            true
+        },
+        |path, mtime| {
+            if let Ok(mut stamps) = stamps.lock() {
+                stamps.push((path.to_path_buf(), mtime));
+            }
+        },
+    );
+
+    if let Ok(mut active) = ACTIVE.lock() {
+        active.retain(|s| !Arc::ptr_eq(&s.cancelled, &cancelled));
+    }
+
+    let (files, sent, _) = found.into_inner().unwrap_or_else(|e| e.into_inner());
+    let was_cancelled = cancelled.load(Ordering::Relaxed);
+    let _ = app.emit(
+        "tree-progress",
+        ScanProgress {
+            root: root_str,
+            files_found: files.len(),
+            partial: files[sent..]
+                .iter()
+                .map(|p| p.to_string_lossy().to_string())
+                .collect(),
+            done: !was_cancelled,
+            cancelled: was_cancelled,
+        },
+    );
+
+    if was_cancelled {
+        eprintln!("[mdcat] scan cancelled: {}", root.display());
+        outcome.finish(None);
+        None
+    } else {
+        let stamps = stamps.into_inner().unwrap_or_else(|e| e.into_inner());
+        tree_cache::store(app, root, config, &files, stamps);
+        outcome.finish(Some(files.clone()));
+        Some(files)
+    }
+}
@@ -0,0 +1,533 @@
+//! Recursive watchers for the current root (or every root of a workspace).
+//! Keeps each root's markdown files in memory and emits `tree-changed` diffs,
+//! so the sidebar updates live without the frontend asking for a full rescan.
+
+use crate::book_nav;
+use crate::config::{self, TreeConfig};
+use crate::file_meta::{self, FileMeta};
+use crate::file_tree::{self, ScanScope};
+use crate::git_repo;
+use crate::heading_index;
+use crate::link_graph;
+use crate::quick_open;
+use crate::search_index;
+use crate::tree_cache;
+use crate::tree_scan;
+use notify::event::{AccessKind, AccessMode, EventKind};
+use notify_debouncer_mini::{new_debouncer_opt, Config, DebouncedEvent, Debouncer};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::Emitter;
+
+static WATCHERS: Mutex<Vec<TreeWatch>> = Mutex::new(Vec::new());
+
+/// Event paths buffered while a root is indexing; past this, the replay is
+/// a rescan of the whole root instead.
+const MAX_PENDING: usize = 10_000;
+
+struct TreeWatch {
+    state: Arc<Mutex<TreeState>>,
+    _debouncer: Debouncer<ChangeWatcher>,
+}
+
+/// `RecommendedWatcher` without read access events. inotify reports every
+/// open of a watched file or directory, including the opens of the rescan
+/// an event triggers, which would keep a root rescanning itself.
+struct ChangeWatcher(notify::RecommendedWatcher);
+
+impl notify::Watcher for ChangeWatcher {
+    fn new<F: notify::EventHandler>(
+        mut handler: F,
+        config: notify::Config,
+    ) -> notify::Result<Self> {
+        let filtered = move |res: notify::Result<notify::Event>| {
+            let read_only = res.as_ref().is_ok_and(|e| {
+                matches!(e.kind, EventKind::Access(kind) if kind != AccessKind::Close(AccessMode::Write))
+            });
+            if !read_only {
+                handler.handle_event(res);
+            }
+        };
+        notify::RecommendedWatcher::new(filtered, config).map(ChangeWatcher)
+    }
+
+    fn watch(&mut self, path: &Path, recursive_mode: notify::RecursiveMode) -> notify::Result<()> {
+        self.0.watch(path, recursive_mode)
+    }
+
+    fn unwatch(&mut self, path: &Path) -> notify::Result<()> {
+        self.0.unwatch(path)
+    }
+
+    fn configure(&mut self, option: notify::Config) -> notify::Result<bool> {
+        self.0.configure(option)
+    }
+
+    fn kind() -> notify::WatcherKind {
+        notify::RecommendedWatcher::kind()
+    }
+}
+
+/// In-memory view of one root.
+struct TreeState {
+    root: PathBuf,
+    /// Event paths are canonical on macOS (`/private/var/...`); used to map them back.
+    canonical_root: PathBuf,
+    config: TreeConfig,
+    files: BTreeSet<PathBuf>,
+    /// False until the background full scan has filled `files`.
+    indexed: bool,
+    /// Paths changed while indexing; the walk may already have passed them,
+    /// so they're replayed once `indexed` is set.
+    pending: BTreeSet<PathBuf>,
+    /// Set by `unwatch`; a cancelled index scan is only retried until then.
+    stopped: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenamedPath {
+    pub from: String,
+    pub to: String,
+}
+
+/// Payload of the `tree-changed` event.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TreeDiff {
+    pub root: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub renamed: Vec<RenamedPath>,
+    /// Files whose content changed in place.
+    pub modified: Vec<String>,
+}
+
+impl TreeDiff {
+    /// Added/removed files between two snapshots of `root`, with renames paired up.
+    pub fn between(root: &Path, before: &BTreeSet<PathBuf>, after: &BTreeSet<PathBuf>) -> TreeDiff {
+        let mut added: Vec<PathBuf> = after.difference(before).cloned().collect();
+        let mut removed: Vec<PathBuf> = before.difference(after).cloned().collect();
+        TreeDiff::from_changes(root, &mut added, &mut removed, &HashMap::new())
+    }
+
+    /// `removed_meta`: what was cached about removed files, for telling a
+    /// rename from an unrelated delete + create.
+    fn from_changes(
+        root: &Path,
+        added: &mut Vec<PathBuf>,
+        removed: &mut Vec<PathBuf>,
+        removed_meta: &HashMap<PathBuf, FileMeta>,
+    ) -> TreeDiff {
+        let mut diff = TreeDiff {
+            root: root.to_string_lossy().to_string(),
+            ..TreeDiff::default()
+        };
+        pair_renames(added, removed, removed_meta, &mut diff.renamed);
+        diff.added = added
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        diff.removed = removed
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.renamed.is_empty()
+            && self.modified.is_empty()
+    }
+}
+
+/// Start watching `roots`, replacing all previous root watches.
+pub fn watch(roots: &[PathBuf], app: tauri::AppHandle) -> Result<(), String> {
+    unwatch();
+
+    let mut watches = Vec::with_capacity(roots.len());
+    for root in roots {
+        watches.push(watch_root(root.clone(), app.clone())?);
+    }
+    if let Ok(mut lock) = WATCHERS.lock() {
+        *lock = watches;
+    }
+    Ok(())
+}
+
+fn watch_root(root: PathBuf, app: tauri::AppHandle) -> Result<TreeWatch, String> {
+    if !root.is_dir() {
+        return Err(format!("Not a directory: {}", root.display()));
+    }
+
+    let state = Arc::new(Mutex::new(TreeState {
+        canonical_root: root.canonicalize().unwrap_or_else(|_| root.clone()),
+        root: root.clone(),
+        config: config::load_for_root(&root).config,
+        files: BTreeSet::new(),
+        indexed: false,
+        pending: BTreeSet::new(),
+        stopped: false,
+    }));
+
+    // Full index in the background; `get_tree_children` serves shallow listings meanwhile.
+    let index_state = Arc::clone(&state);
+    let index_app = app.clone();
+    let index_root = root.clone();
+    std::thread::spawn(move || {
+        let config = match index_state.lock() {
+            Ok(state) => state.config.clone(),
+            Err(_) => return,
+        };
+        // a still-valid on-disk cache skips the walk entirely
+        let cached =
+            tree_cache::load(&index_app, &index_root, &config).filter(|c| c.is_fresh(&index_root));
+        let files = match cached {
+            Some(cached) => cached.files(&index_root),
+            None => loop {
+                if let Some(files) = tree_scan::scan(&index_root, &config, &index_app) {
+                    break files;
+                }
+                // cancelled: scan again unless the watch itself is gone
+                match index_state.lock() {
+                    Ok(state) if !state.stopped => {}
+                    Ok(mut state) => {
+                        state.pending.clear();
+                        return;
+                    }
+                    Err(_) => return,
+                }
+            },
+        };
+        let replayed = match index_state.lock() {
+            Ok(mut state) => {
+                state.files = files.into_iter().collect();
+                state.indexed = true;
+                let pending = std::mem::take(&mut state.pending);
+                state.apply_events(pending.into_iter())
+            }
+            Err(_) => return,
+        };
+        let _ = index_app.emit("tree-indexed", index_root.to_string_lossy().to_string());
+        publish(&index_app, replayed);
+    });
+
+    let callback_state = Arc::clone(&state);
+    let mut debouncer = new_debouncer_opt::<_, ChangeWatcher>(
+        Config::default().with_timeout(Duration::from_millis(200)),
+        move |res: Result<Vec<DebouncedEvent>, notify::Error>| {
+            let Ok(events) = res else {
+                return;
+            };
+            let changes = match callback_state.lock() {
+                Ok(mut state) => state.apply_events(events.into_iter().map(|e| e.path)),
+                Err(_) => return,
+            };
+            publish(&app, changes);
+        },
+    )
+    .map_err(|e| format!("Failed to create watcher: {}", e))?;
+
+    debouncer
+        .watcher()
+        .watch(&root, notify::RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch directory: {}", e))?;
+
+    Ok(TreeWatch {
+        state,
+        _debouncer: debouncer,
+    })
+}
+
+/// Bring a watched root's files in line with a full scan made outside the
+/// watcher (the tree cache's reconcile), then publish `diff`, what the scan
+/// changed.
+pub fn publish_scan(app: &tauri::AppHandle, root: &Path, after: BTreeSet<PathBuf>, diff: TreeDiff) {
+    if let Ok(lock) = WATCHERS.lock() {
+        for watch in lock.iter() {
+            if let Ok(mut state) = watch.state.lock() {
+                // an unindexed root takes its files from its own index scan
+                if state.root == root && state.indexed {
+                    state.files = after;
+                    break;
+                }
+            }
+        }
+    }
+    publish(app, (diff, false));
+}
+
+/// Emit the result of `apply_events` and pass the diff on to the indexes.
+fn publish(app: &tauri::AppHandle, (diff, git_changed): (TreeDiff, bool)) {
+    if !diff.is_empty() {
+        let _ = app.emit("tree-changed", &diff);
+        quick_open::invalidate(Path::new(&diff.root));
+        heading_index::apply_diff(&diff);
+        link_graph::apply_diff(&diff);
+        search_index::apply_diff(app, &diff);
+    }
+    if git_changed {
+        let _ = app.emit("git-status-changed", &diff.root);
+    }
+}
+
+pub fn unwatch() {
+    if let Ok(mut lock) = WATCHERS.lock() {
+        for watch in lock.drain(..) {
+            if let Ok(mut state) = watch.state.lock() {
+                state.stopped = true;
+                tree_scan::cancel(&state.root);
+                quick_open::invalidate(&state.root);
+            }
+        }
+    }
+}
+
+/// Run `f` on the in-memory state if `root` is a watched root and its
+/// background index is complete.
+fn with_indexed<T>(root: &Path, f: impl FnOnce(&TreeState) -> T) -> Option<T> {
+    let lock = WATCHERS.lock().ok()?;
+    for watch in lock.iter() {
+        let state = watch.state.lock().ok()?;
+        if state.root == root {
+            return state.indexed.then(|| f(&state));
+        }
+    }
+    None
+}
+
+/// Indexed markdown files under `dir` (see `with_indexed`).
+pub fn cached_files(root: &Path, dir: &Path) -> Option<Vec<PathBuf>> {
+    with_indexed(root, |state| state.files_under(dir).cloned().collect())
+}
+
+impl TreeState {
+    /// Map an event path into `root`'s namespace; `None` if it lies outside.
+    fn normalize(&self, path: PathBuf) -> Option<PathBuf> {
+        if path.starts_with(&self.root) {
+            return Some(path);
+        }
+        let rel = path.strip_prefix(&self.canonical_root).ok()?;
+        Some(self.root.join(rel))
+    }
+
+    /// Turn a batch of changed paths into rescans of the smallest affected scope.
+    /// Also reports whether the git index or HEAD moved (status decorations are stale).
+    fn apply_events(&mut self, paths: impl Iterator<Item = PathBuf>) -> (TreeDiff, bool) {
+        if !self.indexed {
+            // the running full scan may already be past these; keep them for later
+            self.pending.extend(paths);
+            if self.pending.len() > MAX_PENDING {
+                // the root itself as a changed dir rescans everything
+                self.pending = BTreeSet::from([self.root.clone()]);
+            }
+            return (TreeDiff::default(), false);
+        }
+        let mut scopes: Vec<ScanScope> = Vec::new();
+        let mut touched: BTreeSet<PathBuf> = BTreeSet::new();
+        let mut full_rescan = false;
+        let mut git_changed = false;
+        let mut nav_changed: Vec<String> = Vec::new();
+
+        for path in paths {
+            let Some(path) = self.normalize(path) else {
+                continue;
+            };
+            if path.ends_with(".git/info/exclude") {
+                full_rescan = true;
+                continue;
+            }
+            if path.ends_with(".git/index") || path.ends_with(".git/HEAD") {
+                git_changed = true;
+                continue;
+            }
+            if path.file_name().is_some_and(|n| n == ".git") {
+                // a repo, worktree or submodule appeared or went away
+                git_repo::invalidate();
+                git_changed = true;
+                continue;
+            }
+            let rel = path.strip_prefix(&self.root).unwrap_or(&path);
+            let parent_allowed = rel
+                .parent()
+                .into_iter()
+                .flat_map(|p| p.components())
+                .all(|c| {
+                    self.config
+                        .allows_dir_name(&c.as_os_str().to_string_lossy())
+                });
+            if !parent_allowed {
+                continue;
+            }
+            if file_tree::is_rule_file(&path) {
+                full_rescan = true;
+                continue;
+            }
+            if book_nav::is_nav_config(&path) && path.parent() == Some(self.root.as_path()) {
+                // not markdown, but book order depends on it
+                nav_changed.push(path.to_string_lossy().to_string());
+                continue;
+            }
+            let parent = path.parent().unwrap_or(&self.root).to_path_buf();
+
+            match std::fs::metadata(&path) {
+                Ok(meta) if meta.is_dir() => {
+                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    if path == self.root || self.config.allows_dir_name(&name) {
+                        scopes.push(ScanScope::Dir(path));
+                    }
+                }
+                Ok(_) => {
+                    if self.config.has_markdown_ext(&path) {
+                        if self.files.contains(&path) {
+                            touched.insert(path);
+                        } else {
+                            scopes.push(ScanScope::DirShallow(parent));
+                        }
+                    }
+                }
+                Err(_) => {
+                    // Gone: either a tracked file or a directory holding tracked files.
+                    if self.files.contains(&path) {
+                        scopes.push(ScanScope::DirShallow(parent));
+                    } else if self.files_under(&path).next().is_some() {
+                        scopes.push(ScanScope::Dir(path));
+                    }
+                }
+            }
+        }
+
+        let mut added: Vec<PathBuf> = Vec::new();
+        let mut removed: Vec<PathBuf> = Vec::new();
+        let mut removed_meta: HashMap<PathBuf, FileMeta> = HashMap::new();
+
+        if full_rescan {
+            self.config = config::load_for_root(&self.root).config;
+            scopes = vec![ScanScope::All];
+        } else {
+            scopes.sort_by(|a, b| {
+                scope_dir(a).cmp(scope_dir(b)).then(
+                    matches!(a, ScanScope::DirShallow(_))
+                        .cmp(&matches!(b, ScanScope::DirShallow(_))),
+                )
+            });
+            scopes.dedup();
+        }
+
+        for scope in &scopes {
+            // a recursive scope already covers anything nested below it
+            let covered = scopes.iter().any(|other| {
+                other != scope
+                    && matches!(other, ScanScope::Dir(_) | ScanScope::All)
+                    && scope_dir(scope).starts_with(scope_dir(other))
+            });
+            if covered {
+                continue;
+            }
+
+            let before: BTreeSet<PathBuf> = self.files_in_scope(scope).cloned().collect();
+            let after: BTreeSet<PathBuf> =
+                file_tree::scan_md_files(&self.root, &self.config, scope.clone())
+                    .into_iter()
+                    .collect();
+
+            for path in before.difference(&after) {
+                self.files.remove(path);
+                if let Some(meta) = file_meta::forget(path) {
+                    removed_meta.insert(path.clone(), meta);
+                }
+                removed.push(path.clone());
+            }
+            for path in after.difference(&before) {
+                self.files.insert(path.clone());
+                added.push(path.clone());
+            }
+        }
+
+        let mut diff = TreeDiff::from_changes(&self.root, &mut added, &mut removed, &removed_meta);
+        diff.modified = touched
+            .iter()
+            .filter(|p| self.files.contains(*p))
+            .map(|p| p.to_string_lossy().to_string())
+            .chain(nav_changed)
+            .collect();
+        (diff, git_changed)
+    }
+
+    fn files_under<'a>(&'a self, dir: &'a Path) -> impl Iterator<Item = &'a PathBuf> + 'a {
+        self.files
+            .range(dir.to_path_buf()..)
+            .take_while(move |p| p.starts_with(dir))
+    }
+
+    fn files_in_scope<'a>(
+        &'a self,
+        scope: &'a ScanScope,
+    ) -> Box<dyn Iterator<Item = &'a PathBuf> + 'a> {
+        match scope {
+            ScanScope::All => Box::new(self.files.iter()),
+            ScanScope::Dir(dir) => Box::new(self.files_under(dir)),
+            ScanScope::DirShallow(dir) => Box::new(
+                self.files_under(dir)
+                    .filter(move |p| p.parent() == Some(dir.as_path())),
+            ),
+        }
+    }
+}
+
+fn scope_dir(scope: &ScanScope) -> &Path {
+    match scope {
+        ScanScope::All => Path::new(""),
+        ScanScope::Dir(p) | ScanScope::DirShallow(p) => p,
+    }
+}
+
+/// Whether `added` has the size and mtime last seen for a removed file; a
+/// rename keeps both, an unrelated new file almost never does.
+fn same_file(added: &Path, removed: Option<&FileMeta>) -> bool {
+    let (Some(removed), Some(added)) = (removed, file_meta::stat(added)) else {
+        return false;
+    };
+    added == (removed.mtime_ms, removed.size)
+}
+
+/// Pair removed/added files that look like the same document moving: same
+/// file name, or a lone remove + add in one batch whose size and mtime match.
+fn pair_renames(
+    added: &mut Vec<PathBuf>,
+    removed: &mut Vec<PathBuf>,
+    removed_meta: &HashMap<PathBuf, FileMeta>,
+    renamed: &mut Vec<RenamedPath>,
+) {
+    let mut i = 0;
+    while i < removed.len() {
+        let name = removed[i].file_name();
+        let same_name: Vec<usize> = added
+            .iter()
+            .enumerate()
+            .filter(|(_, a)| a.file_name() == name)
+            .map(|(j, _)| j)
+            .collect();
+        if same_name.len() == 1 {
+            let to = added.remove(same_name[0]);
+            let from = removed.remove(i);
+            renamed.push(RenamedPath {
+                from: from.to_string_lossy().to_string(),
+                to: to.to_string_lossy().to_string(),
+            });
+        } else {
+            i += 1;
+        }
+    }
+
+    if added.len() == 1 && removed.len() == 1 && same_file(&added[0], removed_meta.get(&removed[0]))
+    {
+        renamed.push(RenamedPath {
+            from: removed.remove(0).to_string_lossy().to_string(),
+            to: added.remove(0).to_string_lossy().to_string(),
+        });
+    }
+}
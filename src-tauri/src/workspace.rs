@@ -0,0 +1,169 @@
+//! Multi-root workspaces: a small TOML file listing several roots that share
+//! one window, each shown as its own top-level tree section.
+//!
+//! ```toml
+//! name = "Docs"
+//!
+//! [[roots]]
+//! path = "../api"
+//!
+//! [[roots]]
+//! path = "../web"
+//! name = "Web app"
+//! ```
+//!
+//! Relative root paths are resolved against the workspace file's directory.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+
+/// Extension of workspace files, e.g. `docs.mdcat-workspace`.
+pub const WORKSPACE_EXTENSION: &str = "mdcat-workspace";
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WorkspaceFile {
+    name: Option<String>,
+    #[serde(default)]
+    roots: Vec<RootEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RootEntry {
+    path: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceRoot {
+    /// Absolute path of the root directory.
+    pub path: PathBuf,
+    /// Section label; defaults to the directory name.
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Workspace {
+    /// The workspace file this was loaded from or saved to.
+    pub path: PathBuf,
+    pub name: String,
+    pub roots: Vec<WorkspaceRoot>,
+}
+
+impl Workspace {
+    /// Member root containing `path` (the innermost one if roots nest).
+    pub fn member_root(&self, path: &Path) -> Option<&WorkspaceRoot> {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.roots
+            .iter()
+            .filter(|r| path.starts_with(&r.path) || canonical.starts_with(&r.path))
+            .max_by_key(|r| r.path.components().count())
+    }
+
+    pub fn root_paths(&self) -> Vec<PathBuf> {
+        self.roots.iter().map(|r| r.path.clone()).collect()
+    }
+}
+
+pub fn is_workspace_file(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == WORKSPACE_EXTENSION)
+}
+
+fn default_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string())
+}
+
+/// Read a workspace file. Roots that no longer exist are skipped with a log line.
+pub fn load(path: &Path) -> Result<Workspace, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let file: WorkspaceFile = toml::from_str(&contents)
+        .map_err(|e| format!("Invalid workspace {}: {}", path.display(), e))?;
+    let base = path.parent().unwrap_or(Path::new(""));
+
+    let mut roots: Vec<WorkspaceRoot> = Vec::new();
+    for entry in file.roots {
+        let Ok(dir) = base.join(&entry.path).canonicalize() else {
+            eprintln!("[mdcat] workspace root missing: {}", entry.path.display());
+            continue;
+        };
+        if !dir.is_dir() || roots.iter().any(|r| r.path == dir) {
+            continue;
+        }
+        roots.push(WorkspaceRoot {
+            name: entry.name.unwrap_or_else(|| default_name(&dir)),
+            path: dir,
+        });
+    }
+    if roots.is_empty() {
+        return Err(format!(
+            "Workspace {} has no existing roots",
+            path.display()
+        ));
+    }
+
+    Ok(Workspace {
+        name: file
+            .name
+            .unwrap_or_else(|| default_name(&path.with_extension(""))),
+        path: path.to_path_buf(),
+        roots,
+    })
+}
+
+/// Write a workspace file listing `roots` (stored relative to the file when
+/// possible, so the file can be committed next to the repos), then load it back.
+/// An empty root name means "use the directory name".
+pub fn save(
+    path: &Path,
+    name: Option<String>,
+    roots: &[WorkspaceRoot],
+) -> Result<Workspace, String> {
+    let base = path.parent().unwrap_or(Path::new(""));
+    let base = base.canonicalize().unwrap_or_else(|_| base.to_path_buf());
+    let file = WorkspaceFile {
+        name,
+        roots: roots
+            .iter()
+            .map(|r| RootEntry {
+                path: relative_to(&r.path, &base),
+                name: Some(r.name.clone()).filter(|n| !n.is_empty() && *n != default_name(&r.path)),
+            })
+            .collect(),
+    };
+    let contents =
+        toml::to_string_pretty(&file).map_err(|e| format!("Failed to encode workspace: {}", e))?;
+    std::fs::write(path, contents)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    load(path)
+}
+
+/// `path` relative to `base` (with `..` as needed) when both are absolute on
+/// the same prefix; otherwise `path` unchanged.
+fn relative_to(path: &Path, base: &Path) -> PathBuf {
+    let path_parts: Vec<Component> = path.components().collect();
+    let base_parts: Vec<Component> = base.components().collect();
+    if path_parts.first() != base_parts.first() {
+        return path.to_path_buf();
+    }
+    let common = path_parts
+        .iter()
+        .zip(&base_parts)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut rel = PathBuf::new();
+    for _ in common..base_parts.len() {
+        rel.push("..");
+    }
+    for part in &path_parts[common..] {
+        rel.push(part);
+    }
+    if rel.as_os_str().is_empty() {
+        rel.push(".");
+    }
+    rel
+}
@@ -0,0 +1,280 @@
+//! Search and replace across every markdown file of the open roots, in two
+//! steps: `preview` lists each match with its line before and after, and
+//! `plan` recomputes the selected replacements for writing. Each previewed
+//! file carries a content stamp; files whose stamp no longer matches are
+//! refused rather than edited blind.
+
+use crate::config::TreeConfig;
+use crate::file_tree::{self, ScanScope};
+use crate::tree_watcher;
+use crate::workspace_search::{self, SearchOptions};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// Most edits listed in one preview; the rest are dropped and flagged.
+const MAX_EDITS: usize = 10_000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplaceEdit {
+    /// Position among this file's matches; what `ReplaceSelection::edits` refers to.
+    pub index: usize,
+    /// 1-based line and char column of the match start.
+    pub line: usize,
+    pub column: usize,
+    pub matched: String,
+    /// `matched` after replacement (capture groups expanded).
+    pub replacement: String,
+    /// The line holding the match, as it is and as it'd become.
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileReplace {
+    pub path: String,
+    /// Content hash at preview time; pass it back to apply.
+    pub stamp: String,
+    pub edits: Vec<ReplaceEdit>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplacePreview {
+    pub files: Vec<FileReplace>,
+    pub total_edits: usize,
+    /// Set when more than `MAX_EDITS` matches were found and the rest left out.
+    pub truncated: bool,
+}
+
+/// The edits of one previewed file to apply.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReplaceSelection {
+    pub path: String,
+    pub stamp: String,
+    pub edits: Vec<usize>,
+}
+
+/// A file of a plan: its content as read, to restore on failure, and as
+/// it'll be written.
+#[derive(Debug)]
+pub struct FileWrite {
+    pub path: PathBuf,
+    pub original: String,
+    pub updated: String,
+}
+
+/// Result of `plan`: new contents to write, and files left alone.
+#[derive(Debug, Default)]
+pub struct ReplacePlan {
+    pub writes: Vec<FileWrite>,
+    pub replacements: usize,
+    /// Changed on disk (or unreadable) since the preview.
+    pub refused: Vec<String>,
+}
+
+/// Content hash that previews hand out and writes check, so files changed
+/// since the preview are left alone.
+pub fn stamp(content: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    format!("{:x}", hasher.finish())
+}
+
+/// The replacement text for one match: capture groups (`$1`, `${name}`) are
+/// expanded in regex mode, literal mode inserts `replacement` as is.
+fn expand(caps: &Captures, replacement: &str, options: SearchOptions) -> String {
+    if options.regex {
+        let mut out = String::new();
+        caps.expand(replacement, &mut out);
+        out
+    } else {
+        replacement.to_string()
+    }
+}
+
+/// Non-empty matches of `matcher` in `content`, taken line by line as
+/// `workspace_search` does (so `^` and `$` anchor to lines and no match spans
+/// a line break), each with its line and the line's byte offset.
+fn matches<'a>(
+    matcher: &'a Regex,
+    content: &'a str,
+) -> impl Iterator<Item = (usize, &'a str, Captures<'a>)> + 'a {
+    content
+        .split_inclusive('\n')
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            let line = match line.strip_suffix('\n') {
+                Some(line) => line.strip_suffix('\r').unwrap_or(line),
+                None => line,
+            };
+            Some((start, line))
+        })
+        .flat_map(move |(start, line)| {
+            matcher
+                .captures_iter(line)
+                .filter(|caps| caps.get(0).is_some_and(|m| !m.is_empty()))
+                .map(move |caps| (start, line, caps))
+        })
+}
+
+fn preview_file(
+    path: &Path,
+    matcher: &Regex,
+    replacement: &str,
+    options: SearchOptions,
+    budget: usize,
+) -> Option<FileReplace> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let mut edits = Vec::new();
+    for (index, (start, line, caps)) in matches(matcher, &content).enumerate().take(budget) {
+        let m = caps.get(0)?;
+        let replaced = expand(&caps, replacement, options);
+        edits.push(ReplaceEdit {
+            index,
+            line: content[..start].matches('\n').count() + 1,
+            column: line[..m.start()].chars().count() + 1,
+            matched: m.as_str().to_string(),
+            before: line.to_string(),
+            after: format!("{}{}{}", &line[..m.start()], replaced, &line[m.end()..]),
+            replacement: replaced,
+        });
+    }
+    (!edits.is_empty()).then(|| FileReplace {
+        path: path.to_string_lossy().to_string(),
+        stamp: stamp(&content),
+        edits,
+    })
+}
+
+/// Every match of `query` under `roots` with its replacement.
+pub fn preview(
+    roots: &[(PathBuf, TreeConfig)],
+    query: &str,
+    replacement: &str,
+    options: SearchOptions,
+) -> Result<ReplacePreview, String> {
+    if query.is_empty() {
+        return Err("Empty search query".to_string());
+    }
+    let matcher = workspace_search::build_matcher(query, options)?;
+
+    let mut preview = ReplacePreview {
+        files: Vec::new(),
+        total_edits: 0,
+        truncated: false,
+    };
+    for (root, config) in roots {
+        let files = tree_watcher::cached_files(root, root)
+            .unwrap_or_else(|| file_tree::scan_md_files(root, config, ScanScope::All));
+        for path in files {
+            let budget = MAX_EDITS - preview.total_edits;
+            if budget == 0 {
+                preview.truncated = true;
+                return Ok(preview);
+            }
+            if let Some(file) = preview_file(&path, &matcher, replacement, options, budget) {
+                preview.total_edits += file.edits.len();
+                preview.files.push(file);
+            }
+        }
+    }
+    Ok(preview)
+}
+
+/// New contents for every selected file whose stamp still matches, with
+/// only the selected edits applied.
+pub fn plan(
+    query: &str,
+    replacement: &str,
+    options: SearchOptions,
+    selections: &[ReplaceSelection],
+) -> Result<ReplacePlan, String> {
+    let matcher = workspace_search::build_matcher(query, options)?;
+    let mut plan = ReplacePlan::default();
+
+    for selection in selections.iter().filter(|s| !s.edits.is_empty()) {
+        let path = PathBuf::from(&selection.path);
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) if stamp(&content) == selection.stamp => content,
+            _ => {
+                plan.refused.push(selection.path.clone());
+                continue;
+            }
+        };
+        let chosen: HashSet<usize> = selection.edits.iter().copied().collect();
+        let mut updated = String::with_capacity(content.len());
+        let mut copied = 0;
+        let mut count = 0;
+        for (index, (start, _, caps)) in matches(&matcher, &content).enumerate() {
+            if !chosen.contains(&index) {
+                continue;
+            }
+            let Some(m) = caps.get(0) else {
+                continue;
+            };
+            updated.push_str(&content[copied..start + m.start()]);
+            updated.push_str(&expand(&caps, replacement, options));
+            copied = start + m.end();
+            count += 1;
+        }
+        if count == 0 {
+            continue;
+        }
+        updated.push_str(&content[copied..]);
+        plan.replacements += count;
+        plan.writes.push(FileWrite {
+            path,
+            original: content,
+            updated,
+        });
+    }
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anchored_pattern_agrees_with_search() {
+        let dir = std::env::temp_dir().join(format!("mdcat-replace-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.md");
+        let content = "# One\ntext # not\r\n# Two\nend\n";
+        std::fs::write(&path, content).unwrap();
+        let options = SearchOptions {
+            regex: true,
+            case_sensitive: false,
+        };
+        let matcher = workspace_search::build_matcher("^# ", options).unwrap();
+
+        let searched: Vec<(usize, usize)> = workspace_search::search_file(&path, &matcher)
+            .iter()
+            .map(|m| (m.line, m.column))
+            .collect();
+        let file = preview_file(&path, &matcher, "## ", options, MAX_EDITS).unwrap();
+        let previewed: Vec<(usize, usize)> =
+            file.edits.iter().map(|e| (e.line, e.column)).collect();
+        assert_eq!(searched, vec![(1, 1), (3, 1)]);
+        assert_eq!(previewed, searched);
+
+        let selection = ReplaceSelection {
+            path: file.path.clone(),
+            stamp: file.stamp.clone(),
+            edits: vec![0, 1],
+        };
+        let plan = plan("^# ", "## ", options, &[selection]).unwrap();
+        assert_eq!(
+            plan.writes[0].updated,
+            "## One\ntext # not\r\n## Two\nend\n"
+        );
+
+        let spanning = workspace_search::build_matcher(r"One\s+text", options).unwrap();
+        assert!(preview_file(&path, &spanning, "", options, MAX_EDITS).is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,244 @@
+//! Workspace-wide full-text search over every markdown file the tree shows
+//! (same ignore rules, served from the watcher's index when ready).
+//! Matches stream as `search-results` events; starting a new search cancels
+//! the one before it, so typing never queues up stale work.
+
+use crate::config::TreeConfig;
+use crate::file_tree::{self, ScanScope};
+use crate::tree_watcher;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+
+/// Minimum gap between two `search-results` events for one search.
+const RESULTS_INTERVAL: Duration = Duration::from_millis(100);
+/// Stop collecting after this many matches; the last event says so.
+const MAX_MATCHES: usize = 5_000;
+/// Longest snippet sent per match, in chars.
+const SNIPPET_CHARS: usize = 160;
+/// Context kept before the match when a long line is cut down.
+const SNIPPET_LEAD: usize = 40;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+static ACTIVE: Mutex<Option<Arc<AtomicBool>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    /// Treat the query as a regular expression instead of literal text.
+    pub regex: bool,
+    pub case_sensitive: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchMatch {
+    pub path: String,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column of the match start, in chars.
+    pub column: usize,
+    /// Match length in chars.
+    pub length: usize,
+    /// The matching line, cut around the match when long.
+    pub snippet: String,
+    /// Char offset of the match within `snippet`.
+    pub snippet_offset: usize,
+}
+
+/// Payload of the `search-results` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResults {
+    /// Id returned by `search_workspace`; events of superseded searches can be dropped.
+    pub id: u64,
+    /// Matches found since the previous event for this search.
+    pub matches: Vec<SearchMatch>,
+    pub files_searched: usize,
+    pub files_total: usize,
+    pub done: bool,
+    pub cancelled: bool,
+    /// Set once `MAX_MATCHES` was hit and the search stopped early.
+    pub truncated: bool,
+}
+
+/// Compile `query` under `options`; literal queries are escaped.
+pub fn build_matcher(query: &str, options: SearchOptions) -> Result<Regex, String> {
+    let pattern = if options.regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .build()
+        .map_err(|e| format!("Invalid search pattern: {}", e))
+}
+
+/// Cancel the running search, if any.
+pub fn cancel() {
+    if let Ok(mut active) = ACTIVE.lock() {
+        if let Some(cancelled) = active.take() {
+            cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Start searching every markdown file under `roots` (each with its tree
+/// config) on a background thread, cancelling any previous search.
+/// Returns the id carried by this search's `search-results` events.
+pub fn start(
+    roots: Vec<(PathBuf, TreeConfig)>,
+    query: &str,
+    options: SearchOptions,
+    app: tauri::AppHandle,
+) -> Result<u64, String> {
+    if query.is_empty() {
+        cancel();
+        return Err("Empty search query".to_string());
+    }
+    let matcher = build_matcher(query, options)?;
+
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let cancelled = Arc::new(AtomicBool::new(false));
+    if let Ok(mut active) = ACTIVE.lock() {
+        if let Some(previous) = active.replace(Arc::clone(&cancelled)) {
+            previous.store(true, Ordering::Relaxed);
+        }
+    }
+
+    std::thread::spawn(move || {
+        let files: Vec<PathBuf> = roots
+            .iter()
+            .flat_map(|(root, config)| {
+                tree_watcher::cached_files(root, root)
+                    .unwrap_or_else(|| file_tree::scan_md_files(root, config, ScanScope::All))
+            })
+            .collect();
+        run(id, &files, &matcher, &cancelled, &app);
+        if let Ok(mut active) = ACTIVE.lock() {
+            if active.as_ref().is_some_and(|a| Arc::ptr_eq(a, &cancelled)) {
+                *active = None;
+            }
+        }
+    });
+    Ok(id)
+}
+
+struct Pending {
+    matches: Vec<SearchMatch>,
+    found: usize,
+    files_searched: usize,
+    last_emit: Instant,
+}
+
+/// Search `files` on a few worker threads, batching matches into events.
+fn run(
+    id: u64,
+    files: &[PathBuf],
+    matcher: &Regex,
+    cancelled: &AtomicBool,
+    app: &tauri::AppHandle,
+) {
+    let pending = Mutex::new(Pending {
+        matches: Vec::new(),
+        found: 0,
+        files_searched: 0,
+        last_emit: Instant::now(),
+    });
+    let emit = |pending: &mut Pending, done: bool| {
+        let _ = app.emit(
+            "search-results",
+            SearchResults {
+                id,
+                matches: std::mem::take(&mut pending.matches),
+                files_searched: pending.files_searched,
+                files_total: files.len(),
+                done,
+                cancelled: cancelled.load(Ordering::Relaxed),
+                truncated: pending.found >= MAX_MATCHES,
+            },
+        );
+        pending.last_emit = Instant::now();
+    };
+
+    let next = AtomicUsize::new(0);
+    let workers = std::thread::available_parallelism().map_or(4, |n| n.get().min(8));
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                if cancelled.load(Ordering::Relaxed) {
+                    return;
+                }
+                let Some(path) = files.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                    return;
+                };
+                let found = search_file(path, matcher);
+                let Ok(mut guard) = pending.lock() else {
+                    return;
+                };
+                let room = MAX_MATCHES.saturating_sub(guard.found);
+                guard.found += found.len().min(room);
+                guard.matches.extend(found.into_iter().take(room));
+                guard.files_searched += 1;
+                if guard.found >= MAX_MATCHES {
+                    // stop the other workers; the final event reports `truncated`
+                    next.store(files.len(), Ordering::Relaxed);
+                }
+                if !guard.matches.is_empty() && guard.last_emit.elapsed() >= RESULTS_INTERVAL {
+                    emit(&mut guard, false);
+                }
+            });
+        }
+    });
+
+    let mut pending = pending.into_inner().unwrap_or_else(|e| e.into_inner());
+    let done = !cancelled.load(Ordering::Relaxed);
+    emit(&mut pending, done);
+}
+
+/// Every non-empty match in `path`, line by line. Unreadable or non-UTF-8
+/// files yield nothing.
+pub fn search_file(path: &Path, matcher: &Regex) -> Vec<SearchMatch> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let path_str = path.to_string_lossy().to_string();
+    let mut matches = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        for m in matcher.find_iter(line).filter(|m| !m.is_empty()) {
+            let column = line[..m.start()].chars().count();
+            let length = m.as_str().chars().count();
+            let (snippet, snippet_offset) = snippet(line, column, length);
+            matches.push(SearchMatch {
+                path: path_str.clone(),
+                line: index + 1,
+                column: column + 1,
+                length,
+                snippet,
+                snippet_offset,
+            });
+        }
+    }
+    matches
+}
+
+/// `line` trimmed to at most `SNIPPET_CHARS` around the match at char
+/// `column`, plus the match's offset in the result.
+pub fn snippet(line: &str, column: usize, length: usize) -> (String, usize) {
+    let total = line.chars().count();
+    if total <= SNIPPET_CHARS {
+        return (line.to_string(), column);
+    }
+    let start = column
+        .saturating_sub(SNIPPET_LEAD)
+        .min(total.saturating_sub(SNIPPET_CHARS.max(length)));
+    let text = line
+        .chars()
+        .skip(start)
+        .take(SNIPPET_CHARS.max(length))
+        .collect();
+    (text, column - start)
+}
@@ -37,6 +37,9 @@ export function showContextMenu(x: number, y: number, items: MenuItem[]) {
   menu.setAttribute("role", "menu");
   menu.dataset.testid = "context-menu";
 
+  // Keep labels aligned when only some items carry an icon (e.g. checkmarks)
+  const anyIcon = items.some((item) => item.icon);
+
   for (const item of items) {
     const row = document.createElement("div");
     row.className = "context-menu-item";
@@ -44,7 +47,13 @@ export function showContextMenu(x: number, y: number, items: MenuItem[]) {
     row.dataset.testid = `context-menu-${item.label.toLowerCase().replace(/\s+/g, "-")}`;
     if (item.danger) row.classList.add("danger");
 
-    if (item.icon) row.appendChild(item.icon);
+    if (item.icon) {
+      row.appendChild(item.icon);
+    } else if (anyIcon) {
+      const spacer = document.createElement("span");
+      spacer.className = "context-menu-icon-spacer";
+      row.appendChild(spacer);
+    }
 
     const label = document.createElement("span");
     label.textContent = item.label;
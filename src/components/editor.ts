@@ -18,16 +18,47 @@ import {
 } from "@codemirror/language";
 import { defaultKeymap, history, historyKeymap } from "@codemirror/commands";
 import { search, searchKeymap, highlightSelectionMatches, SearchQuery, setSearchQuery } from "@codemirror/search";
-import { closeBrackets, closeBracketsKeymap } from "@codemirror/autocomplete";
+import {
+  autocompletion,
+  closeBrackets,
+  closeBracketsKeymap,
+  CompletionContext,
+  CompletionResult,
+  completionKeymap,
+} from "@codemirror/autocomplete";
 import { markdown, markdownLanguage } from "@codemirror/lang-markdown";
 import { languages } from "@codemirror/language-data";
 import { keymap } from "@codemirror/view";
 import { getState, setContent, subscribe } from "../state";
+import { completeLink } from "../ipc";
 
 let container: HTMLElement;
 let view: EditorView | null = null;
 let suppressUpdate = false;
 
+/** Destination of a `[text](` or `![alt](` link up to the cursor. */
+const LINK_DEST = /\]\(<?([^\s()<>]*)$/;
+
+async function linkCompletions(context: CompletionContext): Promise<CompletionResult | null> {
+  const from = getState().filePath;
+  const before = context.matchBefore(LINK_DEST);
+  if (!from || !before) return null;
+  const prefix = LINK_DEST.exec(before.text)![1];
+  const completions = await completeLink(from, prefix).catch(() => []);
+  if (context.aborted || completions.length === 0) return null;
+  return {
+    from: before.to - prefix.length,
+    options: completions.map((c, i) => ({
+      label: c.label,
+      detail: c.detail,
+      type: c.kind === "heading" ? "property" : c.kind === "asset" ? "constant" : "text",
+      // keep the backend's ranking
+      boost: 99 - Math.min(i, 198),
+    })),
+    filter: false,
+  };
+}
+
 export function createEditor(): HTMLElement {
   container = document.createElement("div");
   container.style.display = "none";
@@ -60,6 +91,7 @@ function initView(doc: string) {
       syntaxHighlighting(defaultHighlightStyle, { fallback: true }),
       bracketMatching(),
       closeBrackets(),
+      autocompletion({ override: [linkCompletions], activateOnTyping: true }),
       rectangularSelection(),
       crosshairCursor(),
       highlightActiveLine(),
@@ -69,6 +101,7 @@ function initView(doc: string) {
       }),
       keymap.of([
         ...closeBracketsKeymap,
+        ...completionKeymap,
         ...defaultKeymap,
         ...searchKeymap,
         ...historyKeymap,
@@ -1,15 +1,23 @@
 import { getCurrentWindow } from "@tauri-apps/api/window";
-import { FileNode } from "../types";
-import { getState, subscribe, clearFile, setTree } from "../state";
-import { getFileTree, createFile, renameFile, deleteFile } from "../ipc";
+import { listen } from "@tauri-apps/api/event";
+import { ask } from "@tauri-apps/plugin-dialog";
+import { FileMeta, FileNode, ScanProgress, SortKey, SortOrder, TreeDiff, WorkspaceRoot } from "../types";
+import { getState, subscribe, clearFile, setContext, setTree, setWorkspace } from "../state";
+import { getFileTree, getTreeChildren, setTreeSort, createFile, renameFile, previewRename, renameWithLinks, deleteFile, saveWorkspace } from "../ipc";
 import { showContextMenu, MenuItem } from "./context-menu";
 import * as icons from "../utils/icons";
+import { startTreeWatching } from "../utils/watcher";
+import { applyTreeDiff } from "../utils/tree-diff";
 
 let container: HTMLElement;
 let header: HTMLElement;
 let treeWrap: HTMLElement;
 let onSelect: ((path: string) => void) | null = null;
 const manualExpanded = new Set<string>();
+/** Workspace sections already expanded once; later collapses are left alone. */
+const seenSections = new Set<string>();
+/** Files found so far by a running background scan, or null when idle. */
+let scanCount: number | null = null;
 
 export function createFileTree(selectHandler: (path: string) => void): HTMLElement {
   onSelect = selectHandler;
@@ -33,11 +41,17 @@ export function createFileTree(selectHandler: (path: string) => void): HTMLEleme
   subscribe(render);
   render(getState());
 
+  listen<ScanProgress>("tree-progress", (event) => {
+    if (!treeRoots().includes(event.payload.root)) return;
+    scanCount = event.payload.done || event.payload.cancelled ? null : event.payload.files_found;
+    render(getState());
+  });
+
   return container;
 }
 
 function render(state: ReturnType<typeof getState>) {
-  const dirName = state.context?.root.split("/").pop() ?? "";
+  const dirName = state.workspace?.name ?? state.context?.root.split("/").pop() ?? "";
 
   // Rebuild header with label + "+" button
   header.innerHTML = "";
@@ -48,7 +62,29 @@ function render(state: ReturnType<typeof getState>) {
   label.textContent = dirName;
   header.appendChild(label);
 
+  if (scanCount !== null) {
+    const status = document.createElement("span");
+    status.className = "sidebar-header-status";
+    status.dataset.testid = "file-tree-scan-status";
+    status.textContent = `Indexing… ${scanCount}`;
+    header.appendChild(status);
+  }
+
   if (dirName) {
+    const sortBtn = document.createElement("button");
+    sortBtn.className = "sidebar-header-btn";
+    sortBtn.title = "Sort";
+    sortBtn.setAttribute("aria-label", "Sort");
+    sortBtn.dataset.testid = "file-tree-sort-btn";
+    sortBtn.appendChild(icons.arrowUpDown(14));
+    sortBtn.addEventListener("click", (e) => {
+      e.stopPropagation();
+      const root = state.context?.root ?? treeRoots()[0];
+      const rect = sortBtn.getBoundingClientRect();
+      if (root) showSortMenu(rect.left, rect.bottom + 2, root);
+    });
+    header.appendChild(sortBtn);
+
     const addBtn = document.createElement("button");
     addBtn.className = "sidebar-header-btn";
     addBtn.title = "New file";
@@ -57,8 +93,12 @@ function render(state: ReturnType<typeof getState>) {
     addBtn.appendChild(icons.plus(14));
     addBtn.addEventListener("click", (e) => {
       e.stopPropagation();
-      const root = state.context?.root;
-      if (root) showNewFileInput(treeWrap, root, 0, true);
+      const root = state.context?.root ?? treeRoots()[0];
+      if (!root) return;
+      const section = treeWrap.querySelector<HTMLElement>(`.workspace-section[data-path="${CSS.escape(root)}"]`);
+      const parentEl = section?.nextElementSibling as HTMLElement | null;
+      if (parentEl) parentEl.classList.remove("collapsed");
+      showNewFileInput(parentEl ?? treeWrap, root, section ? 1 : 0, true);
     });
     header.appendChild(addBtn);
   }
@@ -66,6 +106,12 @@ function render(state: ReturnType<typeof getState>) {
   treeWrap.innerHTML = "";
   if (state.tree.length === 0) return;
 
+  for (const root of state.workspace?.roots ?? []) {
+    if (seenSections.has(root.path)) continue;
+    seenSections.add(root.path);
+    manualExpanded.add(root.path);
+  }
+
   const ancestorDirs = collectAncestorDirs(state.tree, state.filePath);
   for (const dir of ancestorDirs) manualExpanded.add(dir);
   renderNodes(state.tree, treeWrap, 0, state.filePath, manualExpanded);
@@ -113,6 +159,22 @@ function renderNodes(
   }
 }
 
+/** Levels fetched per expand of a truncated dir. */
+const LAZY_LOAD_DEPTH = 2;
+
+/** Files modified within this window get a "recently changed" dot. */
+const RECENT_MS = 24 * 60 * 60 * 1000;
+
+function describeMeta(meta: FileMeta): string {
+  const lines: string[] = [];
+  if (meta.title) lines.push(meta.title);
+  lines.push(`${meta.word_count} words`);
+  const tasks = meta.tasks_open + meta.tasks_done;
+  if (tasks > 0) lines.push(`${meta.tasks_done}/${tasks} tasks done`);
+  lines.push(`Modified ${new Date(meta.mtime_ms).toLocaleString()}`);
+  return lines.join("\n");
+}
+
 function createCopyBtn(path: string): HTMLElement {
   const btn = document.createElement("button");
   btn.className = "tree-item-copy";
@@ -149,6 +211,14 @@ function renderDir(
   item.className = "tree-item";
   item.dataset.path = node.path;
   item.dataset.type = "dir";
+  if (node.is_hidden) item.classList.add("hidden-entry");
+  if (node.git) item.classList.add(`git-${node.git}`);
+  if (isSection(node, depth)) item.classList.add("workspace-section");
+  if (node.section) item.classList.add("book-section");
+  if (node.nested_repo) {
+    item.classList.add("repo-boundary");
+    item.title = node.nested_repo === "submodule" ? "Submodule" : "Nested repository";
+  }
   item.setAttribute("role", "treeitem");
   item.setAttribute("aria-expanded", String(expanded));
   item.dataset.testid = `tree-dir-${node.name}`;
@@ -161,15 +231,18 @@ function renderDir(
 
   const folderIcon = document.createElement("span");
   folderIcon.className = "icon";
-  folderIcon.appendChild(expanded ? icons.folderOpen() : icons.folder());
+  const dirIcon = (open: boolean) =>
+    node.nested_repo ? icons.gitBranch() : open ? icons.folderOpen() : icons.folder();
+  folderIcon.appendChild(dirIcon(expanded));
   item.appendChild(folderIcon);
 
   const label = document.createElement("span");
   label.className = "tree-item-label";
-  label.textContent = node.name;
+  label.textContent = node.label ?? node.name;
   item.appendChild(label);
 
-  item.appendChild(createCopyBtn(node.path));
+  // book sections have no directory behind them to copy or edit
+  if (!node.section) item.appendChild(createCopyBtn(node.path));
 
   parent.appendChild(item);
 
@@ -183,6 +256,9 @@ function renderDir(
 
   item.addEventListener("click", () => {
     const collapsed = childWrap.classList.toggle("collapsed");
+    if (!collapsed && node.truncated && !node.children) {
+      loadChildren(node, childWrap, depth + 1, activePath, expandedDirs);
+    }
     if (collapsed) {
       manualExpanded.delete(node.path);
     } else {
@@ -192,16 +268,36 @@ function renderDir(
     chevron.innerHTML = "";
     chevron.appendChild(collapsed ? icons.chevronRight(14) : icons.chevronDown(14));
     folderIcon.innerHTML = "";
-    folderIcon.appendChild(collapsed ? icons.folder() : icons.folderOpen());
+    folderIcon.appendChild(dirIcon(!collapsed));
   });
 
   item.addEventListener("contextmenu", (e) => {
     e.preventDefault();
     e.stopPropagation();
-    showDirContextMenu(e.clientX, e.clientY, node, childWrap, depth);
+    if (!node.section) showDirContextMenu(e.clientX, e.clientY, node, childWrap, depth);
   });
 }
 
+/** Fetch and render children of a dir the backend sent `truncated`. */
+async function loadChildren(
+  node: FileNode,
+  childWrap: HTMLElement,
+  depth: number,
+  activePath: string | null,
+  expandedDirs: Set<string>,
+) {
+  const root = rootFor(node.path);
+  if (!root) return;
+  try {
+    node.children = await getTreeChildren(root, node.path, LAZY_LOAD_DEPTH);
+    node.truncated = false;
+    childWrap.innerHTML = "";
+    renderNodes(node.children, childWrap, depth, activePath, expandedDirs);
+  } catch (err) {
+    console.error("[file-tree] load children failed:", err);
+  }
+}
+
 function renderFile(
   node: FileNode,
   parent: HTMLElement,
@@ -212,6 +308,8 @@ function renderFile(
   item.className = "tree-item";
   item.dataset.path = node.path;
   item.dataset.type = "file";
+  if (node.is_hidden) item.classList.add("hidden-entry");
+  if (node.git) item.classList.add(`git-${node.git}`);
   item.setAttribute("role", "treeitem");
   item.dataset.testid = `tree-file-${node.name}`;
   if (node.path === activePath) item.classList.add("active");
@@ -229,9 +327,19 @@ function renderFile(
 
   const label = document.createElement("span");
   label.className = "tree-item-label";
-  label.textContent = node.name;
+  label.textContent = node.label ?? node.name;
   item.appendChild(label);
 
+  if (node.meta) {
+    item.title = describeMeta(node.meta);
+    if (Date.now() - node.meta.mtime_ms < RECENT_MS) {
+      const dot = document.createElement("span");
+      dot.className = "tree-item-recent";
+      dot.dataset.testid = `tree-recent-${node.name}`;
+      item.appendChild(dot);
+    }
+  }
+
   item.appendChild(createCopyBtn(node.path));
 
   item.addEventListener("click", () => {
@@ -367,19 +475,39 @@ function showDirContextMenu(
   x: number, y: number, node: FileNode,
   childWrap: HTMLElement, depth: number,
 ) {
-  const items: MenuItem[] = [
-    {
-      label: "New File",
-      icon: icons.plus(14),
-      action: () => {
-        // Expand directory if collapsed
-        if (childWrap.classList.contains("collapsed")) {
-          childWrap.classList.remove("collapsed");
-          manualExpanded.add(node.path);
-        }
-        showNewFileInput(childWrap, node.path, depth + 1, true);
-      },
+  const newFile: MenuItem = {
+    label: "New File",
+    icon: icons.plus(14),
+    action: () => {
+      // Expand directory if collapsed
+      if (childWrap.classList.contains("collapsed")) {
+        childWrap.classList.remove("collapsed");
+        manualExpanded.add(node.path);
+      }
+      showNewFileInput(childWrap, node.path, depth + 1, true);
     },
+  };
+
+  // A workspace section is a whole root: no rename/delete, only detach it
+  if (isSection(node, depth)) {
+    showContextMenu(x, y, [
+      newFile,
+      {
+        label: "Sort",
+        icon: icons.arrowUpDown(14),
+        action: () => showSortMenu(x, y, node.path),
+      },
+      {
+        label: "Remove from Workspace",
+        icon: icons.iconX(14),
+        action: () => removeWorkspaceRoot(node.path),
+      },
+    ]);
+    return;
+  }
+
+  const items: MenuItem[] = [
+    newFile,
     {
       label: "Rename",
       icon: icons.pencil(14),
@@ -399,8 +527,97 @@ function showDirContextMenu(
   showContextMenu(x, y, items);
 }
 
+// --- Sorting ---
+
+const SORT_ORDERS: [SortOrder, string][] = [
+  ["dirs-first", "Folders First"],
+  ["files-first", "Files First"],
+  ["mixed", "Folders and Files Mixed"],
+];
+
+const SORT_KEYS: [SortKey, string][] = [
+  ["natural", "Name"],
+  ["modified", "Date Modified"],
+  ["title", "Title"],
+  ["name", "Name (Strict)"],
+];
+
+/** Last known sort per root; the context root's comes from its config. */
+const rootSorts = new Map<string, { sort: SortOrder; sort_by: SortKey }>();
+
+function currentSort(root: string): { sort: SortOrder; sort_by: SortKey } {
+  const ctx = getState().context;
+  if (ctx?.root === root) return ctx.config.config;
+  return rootSorts.get(root) ?? { sort: "dirs-first", sort_by: "natural" };
+}
+
+/** Book order is offered for mdBook / MkDocs roots (or while it's the active key). */
+function sortKeys(root: string, sortBy: SortKey): [SortKey, string][] {
+  const ctx = getState().context;
+  const isBook = (ctx?.root === root && ctx.book) || sortBy === "book";
+  return isBook ? [["book", "Book Order"], ...SORT_KEYS] : SORT_KEYS;
+}
+
+/** Pick grouping and key for `root`; the choice is persisted per root by the backend. */
+function showSortMenu(x: number, y: number, root: string) {
+  const { sort, sort_by } = currentSort(root);
+  const mark = (on: boolean) => (on ? icons.check(14) : undefined);
+  showContextMenu(x, y, [
+    ...SORT_ORDERS.map(([value, label]): MenuItem => ({
+      label,
+      icon: mark(value === sort),
+      action: () => applySort(root, value, sort_by),
+    })),
+    ...sortKeys(root, sort_by).map(([value, label]): MenuItem => ({
+      label: value === "book" ? label : `By ${label}`,
+      icon: mark(value === sort_by),
+      action: () => applySort(root, sort, value),
+    })),
+  ]);
+}
+
+async function applySort(root: string, sort: SortOrder, sortBy: SortKey) {
+  try {
+    const loaded = await setTreeSort(root, sort, sortBy);
+    rootSorts.set(root, loaded.config);
+    const ctx = getState().context;
+    if (ctx?.root === root) setContext({ ...ctx, config: loaded });
+    await refreshTree();
+  } catch (err) {
+    console.error("[file-tree] set sort failed:", err);
+  }
+}
+
 // --- Inline rename ---
 
+/** Rename, offering to rewrite the links that point at (or out of) the moved path. */
+async function renameUpdatingLinks(oldPath: string, newPath: string) {
+  const preview = await previewRename(oldPath, newPath);
+  if (preview.total_edits === 0) {
+    await renameFile(oldPath, newPath);
+    return;
+  }
+  const links = preview.total_edits === 1 ? "1 link" : `${preview.total_edits} links`;
+  const files = preview.files.length === 1 ? "1 file" : `${preview.files.length} files`;
+  const listing = preview.files
+    .slice(0, 10)
+    .map((f) => `${f.rel_path}: ${f.edits.map((e) => `${e.from} → ${e.to}`).join(", ")}`)
+    .join("\n");
+  const update = await ask(`Update ${links} in ${files}?\n\n${listing}`, {
+    title: "Rename",
+    okLabel: "Update Links",
+    cancelLabel: "Don't Update",
+  });
+  if (!update) {
+    await renameFile(oldPath, newPath);
+    return;
+  }
+  const outcome = await renameWithLinks(oldPath, newPath, preview.files);
+  for (const path of outcome.refused) {
+    console.warn(`Links in ${path} not updated: file changed since the preview`);
+  }
+}
+
 function startRename(node: FileNode, item: HTMLElement) {
   const label = item.querySelector(".tree-item-label") as HTMLElement;
   if (!label) return;
@@ -427,7 +644,7 @@ function startRename(node: FileNode, item: HTMLElement) {
       // Cancel — restore label
       const restored = document.createElement("span");
       restored.className = "tree-item-label";
-      restored.textContent = node.name;
+      restored.textContent = node.label ?? node.name;
       input.replaceWith(restored);
       return;
     }
@@ -436,7 +653,7 @@ function startRename(node: FileNode, item: HTMLElement) {
     const newPath = `${parentDir}/${newName}`;
 
     try {
-      await renameFile(node.path, newPath);
+      await renameUpdatingLinks(node.path, newPath);
       const state = getState();
       // If we renamed the active file, reopen it at new path
       if (state.filePath === node.path && onSelect) {
@@ -447,7 +664,7 @@ function startRename(node: FileNode, item: HTMLElement) {
       console.error("Rename failed:", err);
       const restored = document.createElement("span");
       restored.className = "tree-item-label";
-      restored.textContent = node.name;
+      restored.textContent = node.label ?? node.name;
       input.replaceWith(restored);
     }
   }
@@ -462,7 +679,7 @@ function startRename(node: FileNode, item: HTMLElement) {
       committed = true; // prevent blur from committing
       const restored = document.createElement("span");
       restored.className = "tree-item-label";
-      restored.textContent = node.name;
+      restored.textContent = node.label ?? node.name;
       input.replaceWith(restored);
     }
   });
@@ -621,11 +838,113 @@ function findNode(nodes: FileNode[], path: string): FileNode | null {
   return null;
 }
 
+// --- Workspace sections ---
+
+/** Roots shown in the sidebar: every workspace root, else the context root. */
+export function treeRoots(): string[] {
+  const { workspace, context } = getState();
+  if (workspace) return workspace.roots.map((r) => r.path);
+  return context ? [context.root] : [];
+}
+
+/** The sidebar root containing `path` (the innermost one if roots nest). */
+function rootFor(path: string): string | undefined {
+  return treeRoots()
+    .filter((root) => path === root || path.startsWith(`${root}/`))
+    .sort((a, b) => b.length - a.length)[0];
+}
+
+function isSection(node: FileNode, depth: number): boolean {
+  return depth === 0 && !!getState().workspace?.roots.some((r) => r.path === node.path);
+}
+
+function sectionNode(root: WorkspaceRoot, children: FileNode[]): FileNode {
+  return { name: root.name, path: root.path, is_dir: true, is_hidden: false, children };
+}
+
+/**
+ * Load the tree of every sidebar root with `load` (full trees by default).
+ * With a workspace open, each root becomes a top-level section node.
+ */
+export async function fetchTree(
+  load: (root: string) => Promise<FileNode[]> = getFileTree,
+): Promise<FileNode[]> {
+  const { workspace } = getState();
+  if (!workspace) {
+    const [root] = treeRoots();
+    return root ? load(root) : [];
+  }
+  return Promise.all(workspace.roots.map(async (r) => sectionNode(r, await load(r.path))));
+}
+
+function hasBookNodes(nodes: FileNode[]): boolean {
+  return nodes.some((n) => n.section || n.label !== undefined || (n.children && hasBookNodes(n.children)));
+}
+
+const NAV_FILES = ["book.toml", "mkdocs.yml", "mkdocs.yaml", "SUMMARY.md"];
+
+/** Whether `diff` touches a root-level file that makes `diff.root` a book (or not). */
+function touchesNav(diff: TreeDiff): boolean {
+  const paths = [...diff.added, ...diff.removed, ...diff.modified, ...diff.renamed.flatMap((r) => [r.from, r.to])];
+  return paths.some((p) => NAV_FILES.some((name) => p === `${diff.root}/${name}`));
+}
+
+/**
+ * Patch the loaded tree of `diff.root` with its changes instead of refetching
+ * every root. Book order is rebuilt whole, since the nav decides where pages go,
+ * and so is a root whose nav files changed, since they decide whether it's a book.
+ */
+async function updateTree(diff: TreeDiff) {
+  const tree = getState().tree;
+  const nodes = getState().workspace ? tree.find((n) => n.path === diff.root)?.children : tree;
+  if (!nodes || hasBookNodes(nodes) || touchesNav(diff)) {
+    setTree(await fetchTree());
+    return;
+  }
+  await applyTreeDiff(nodes, diff, (root, dir) => getTreeChildren(root, dir, 1));
+  if (getState().tree === tree) setTree(tree);
+}
+
+/**
+ * Watch every sidebar root and show its first level right away; the backend
+ * indexes in the background, and the (capped) full trees are swapped in once
+ * "tree-indexed" or "tree-changed" arrives. Later diffs patch the tree in place.
+ */
+export async function loadSidebar() {
+  let fullTreeLoaded = false;
+  const started = await startTreeWatching(treeRoots(), async (diff) => {
+    const patch = fullTreeLoaded ? diff : null;
+    fullTreeLoaded = true;
+    try {
+      if (patch) await updateTree(patch);
+      else setTree(await fetchTree());
+    } catch (err) {
+      // "Scan cancelled" when the root switched mid-scan
+      console.warn("[file-tree] tree refresh skipped:", err);
+    }
+  });
+  if (started) {
+    const firstLevel = await fetchTree((root) => getTreeChildren(root, root, 1));
+    if (!fullTreeLoaded) setTree(firstLevel);
+  }
+}
+
+async function removeWorkspaceRoot(path: string) {
+  const workspace = getState().workspace;
+  if (!workspace) return;
+  const roots = workspace.roots.filter((r) => r.path !== path);
+  if (roots.length === 0) return;
+  try {
+    setWorkspace(await saveWorkspace(workspace.path, workspace.name, roots));
+    await loadSidebar();
+  } catch (err) {
+    console.error("[file-tree] remove workspace root failed:", err);
+  }
+}
+
 // --- Refresh ---
 
 async function refreshTree() {
-  const root = getState().context?.root;
-  if (!root) return;
-  const tree = await getFileTree(root);
-  setTree(tree);
+  if (treeRoots().length === 0) return;
+  setTree(await fetchTree());
 }
@@ -49,7 +49,7 @@ export function createLayout(
   `;
   contentPane.appendChild(empty);
 
-  const preview = createPreview();
+  const preview = createPreview(onFileSelect);
   contentPane.appendChild(preview);
 
   const editor = createEditor();
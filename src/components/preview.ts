@@ -1,4 +1,5 @@
 import { openUrl } from "@tauri-apps/plugin-opener";
+import { resolveWikilink } from "../ipc";
 import { getState, subscribe, toggleMode } from "../state";
 import { SearchState } from "../types";
 import { renderMarkdown } from "../utils/markdown";
@@ -12,7 +13,7 @@ let wrap: HTMLElement;
 let lastContent = "";
 let lastSearch = { open: false, query: "", index: -1, caseSensitive: false };
 
-export function createPreview(): HTMLElement {
+export function createPreview(onOpenFile: (path: string) => void): HTMLElement {
   container = document.createElement("div");
   container.style.display = "block";
   container.style.height = "100%";
@@ -30,6 +31,10 @@ export function createPreview(): HTMLElement {
     const href = anchor.getAttribute("href");
     if (!href) return;
     e.preventDefault();
+    if (anchor.dataset.wikilink != null) {
+      followWikilink(anchor.dataset.wikilink, onOpenFile);
+      return;
+    }
     if (href.startsWith("#")) {
       const target = wrap.querySelector(`[id="${CSS.escape(href.slice(1))}"]`);
       if (target) {
@@ -59,6 +64,25 @@ export function createPreview(): HTMLElement {
   return container;
 }
 
+async function followWikilink(text: string, onOpenFile: (path: string) => void) {
+  const from = getState().filePath;
+  if (!from) return;
+  const res = await resolveWikilink(from, text);
+  if (!res.path) {
+    const why = res.candidates.length ? `ambiguous: ${res.candidates.join(", ")}` : "no such page";
+    console.warn(`[[${text}]]: ${why}`);
+    return;
+  }
+  if (res.path !== from) {
+    onOpenFile(res.path);
+    return;
+  }
+  if (res.slug) {
+    const target = wrap.querySelector(`[id="${CSS.escape(res.slug)}"]`);
+    target?.scrollIntoView({ behavior: "smooth", block: "start" });
+  }
+}
+
 function render(state: ReturnType<typeof getState>) {
   container.style.display = state.mode === "preview" ? "block" : "none";
 
@@ -1,5 +1,33 @@
 import { invoke } from "@tauri-apps/api/core";
-import { FileContext, FileNode } from "./types";
+import {
+  AssetReport,
+  Backlink,
+  FileContext,
+  FileEdits,
+  FileNode,
+  Heading,
+  HeadingMatch,
+  IndexHit,
+  Link,
+  LinkCompletion,
+  LinkReport,
+  LoadedConfig,
+  QuickOpenMatch,
+  RenameOutcome,
+  RenamePreview,
+  ReplaceOutcome,
+  ReplacePreview,
+  ReplaceSelection,
+  RestoreOutcome,
+  SortKey,
+  SortOrder,
+  TrashEntry,
+  TrashOutcome,
+  Workspace,
+  WorkspaceRoot,
+  WikiResolution,
+  WorkspaceSearchOptions,
+} from "./types";
 
 export function getContext(path: string): Promise<FileContext> {
   return invoke("get_context", { path });
@@ -9,6 +37,14 @@ export function getFileTree(root: string): Promise<FileNode[]> {
   return invoke("get_file_tree", { root });
 }
 
+export function getTreeChildren(root: string, dir: string, depth: number): Promise<FileNode[]> {
+  return invoke("get_tree_children", { root, dir, depth });
+}
+
+export function setTreeSort(root: string, sort: SortOrder, sortBy: SortKey): Promise<LoadedConfig> {
+  return invoke("set_tree_sort", { root, sort, sortBy });
+}
+
 export function readFile(path: string): Promise<string> {
   return invoke("read_file", { path });
 }
@@ -25,6 +61,22 @@ export function setCurrentRoot(root: string): Promise<void> {
   return invoke("set_current_root", { root });
 }
 
+export function openWorkspace(path: string): Promise<Workspace> {
+  return invoke("open_workspace", { path });
+}
+
+export function saveWorkspace(path: string, name: string | null, roots: WorkspaceRoot[]): Promise<Workspace> {
+  return invoke("save_workspace", { path, name, roots });
+}
+
+export function closeWorkspace(): Promise<void> {
+  return invoke("close_workspace");
+}
+
+export function getWorkspace(): Promise<Workspace | null> {
+  return invoke("get_workspace");
+}
+
 export function createFile(path: string): Promise<void> {
   return invoke("create_file", { path });
 }
@@ -33,6 +85,20 @@ export function renameFile(oldPath: string, newPath: string): Promise<void> {
   return invoke("rename_file", { oldPath, newPath });
 }
 
+/** Link edits that moving `oldPath` to `newPath` would make across its root. */
+export function previewRename(oldPath: string, newPath: string): Promise<RenamePreview> {
+  return invoke("preview_rename", { oldPath, newPath });
+}
+
+/** Move `oldPath` to `newPath`, rewriting the links of the `approved` files from `previewRename`; stale files are refused. */
+export function renameWithLinks(
+  oldPath: string,
+  newPath: string,
+  approved: FileEdits[],
+): Promise<RenameOutcome> {
+  return invoke("rename_with_links", { oldPath, newPath, approved });
+}
+
 export function deleteFile(path: string): Promise<void> {
   return invoke("delete_file", { path });
 }
@@ -45,6 +111,105 @@ export function unwatchFile(): Promise<void> {
   return invoke("unwatch_file");
 }
 
+export function watchTree(roots: string[]): Promise<void> {
+  return invoke("watch_tree", { roots });
+}
+
+export function unwatchTree(): Promise<void> {
+  return invoke("unwatch_tree");
+}
+
+/** Starts a background search; results arrive as `search-results` events carrying the returned id. */
+export function searchWorkspace(roots: string[], query: string, options: WorkspaceSearchOptions): Promise<number> {
+  return invoke("search_workspace", { roots, query, options });
+}
+
+export function cancelSearch(): Promise<void> {
+  return invoke("cancel_search");
+}
+
+/** Ranked hits from the persistent index; supports `"phrases"` and `prefix*`, and the last word is a prefix while typing. */
+export function rankedSearch(roots: string[], query: string, limit?: number): Promise<IndexHit[]> {
+  return invoke("ranked_search", { roots, query, limit });
+}
+
+/** Fuzzy-match paths and titles in the open workspace or root; an empty query lists recent files first. */
+export function quickOpen(query: string, limit?: number): Promise<QuickOpenMatch[]> {
+  return invoke("quick_open", { query, limit });
+}
+
+/** Fuzzy-match headings in every file of the open workspace or root. */
+export function searchHeadings(query: string, limit?: number): Promise<HeadingMatch[]> {
+  return invoke("search_headings", { query, limit });
+}
+
+export function getOutline(path: string): Promise<Heading[]> {
+  return invoke("get_outline", { path });
+}
+
+/** Links in other files of the root that point at `path`. */
+export function getBacklinks(path: string): Promise<Backlink[]> {
+  return invoke("get_backlinks", { path });
+}
+
+export function getOutgoingLinks(path: string): Promise<Link[]> {
+  return invoke("get_outgoing_links", { path });
+}
+
+/** Where `[[text]]` written in `fromPath` leads; `candidates` lists every match when the name is ambiguous. */
+export function resolveWikilink(fromPath: string, text: string): Promise<WikiResolution> {
+  return invoke("resolve_wikilink", { fromPath, text });
+}
+
+/** Link destinations for `prefix` typed in `fromPath`: relative paths to files and assets, or `#heading` slugs after a `#`. */
+export function completeLink(fromPath: string, prefix: string, limit?: number): Promise<LinkCompletion[]> {
+  return invoke("complete_link", { fromPath, prefix, limit });
+}
+
+/** Relative links and `#anchors` under `root` that don't resolve. */
+export function checkLinks(root: string): Promise<LinkReport> {
+  return invoke("check_links", { root });
+}
+
+/** Assets under `root` no page or site file references, and references to missing assets. */
+export function getAssetReport(root: string): Promise<AssetReport> {
+  return invoke("get_asset_report", { root });
+}
+
+/** Move unused assets of `root` to the app's trash folder; ones referenced again since the report are refused. */
+export function trashUnusedAssets(root: string, paths: string[]): Promise<TrashOutcome> {
+  return invoke("trash_unused_assets", { root, paths });
+}
+
+/** Trashed assets that can still be put back, newest trashing first. */
+export function listTrash(): Promise<TrashEntry[]> {
+  return invoke("list_trash");
+}
+
+/** Put `files` of trashing `id` back under their root; ones whose path is taken again are refused. */
+export function restoreTrashed(id: string, files: string[]): Promise<RestoreOutcome> {
+  return invoke("restore_trashed", { id, files });
+}
+
+/** Every match in the open workspace or root with its replaced line; regex mode expands `$1` / `${name}`. */
+export function previewReplace(
+  query: string,
+  replacement: string,
+  options: WorkspaceSearchOptions,
+): Promise<ReplacePreview> {
+  return invoke("preview_replace", { query, replacement, options });
+}
+
+/** Apply selected edits from `previewReplace` (same query, replacement and options); stale files are refused, and a failed write restores the files already written. */
+export function applyReplace(
+  query: string,
+  replacement: string,
+  options: WorkspaceSearchOptions,
+  selections: ReplaceSelection[],
+): Promise<ReplaceOutcome> {
+  return invoke("apply_replace", { query, replacement, options, selections });
+}
+
 export function dumpStateToFile(stateJson: string): Promise<void> {
   return invoke("dump_state_to_file", { stateJson });
 }
@@ -1,12 +1,12 @@
-import { open } from "@tauri-apps/plugin-dialog";
+import { open, save } from "@tauri-apps/plugin-dialog";
 import { listen } from "@tauri-apps/api/event";
 import { getCurrentWindow } from "@tauri-apps/api/window";
-import { getContext, getFileTree, readFile, saveFile, getOpenedFile, setCurrentRoot, dumpStateToFile, benchReady } from "./ipc";
-import { getState, setFile, setContext, setTree, markClean, toggleSearch } from "./state";
+import { getContext, readFile, saveFile, getOpenedFile, setCurrentRoot, dumpStateToFile, benchReady, openWorkspace, saveWorkspace, getWorkspace } from "./ipc";
+import { getState, setFile, setContext, setWorkspace, markClean, toggleSearch } from "./state";
 import { syncToggleMode } from "./utils/scroll-sync";
 import { FileNode } from "./types";
 import { createLayout } from "./components/layout";
-import { renameActiveFile } from "./components/file-tree";
+import { renameActiveFile, loadSidebar } from "./components/file-tree";
 import { startWatching } from "./utils/watcher";
 import { exposeMdcatAPI } from "./utils/state-bridge";
 
@@ -18,6 +18,11 @@ exposeMdcatAPI();
 // --- File operations ---
 
 async function openFile(path: string) {
+  if (path.endsWith(WORKSPACE_SUFFIX)) {
+    await openWorkspaceFile(path);
+    return;
+  }
+
   // Phase 1: Load content + context in parallel (critical path)
   const [content, ctx] = await Promise.all([readFile(path), getContext(path)]);
 
@@ -38,8 +43,7 @@ async function openFile(path: string) {
   // Phase 2: Defer non-critical work (tree, watcher, root registration)
   (async () => {
     await setCurrentRoot(ctx.root);
-    const tree = await getFileTree(ctx.root);
-    setTree(tree);
+    await loadSidebar();
 
     // Watch for external changes
     await startWatching(path, async () => {
@@ -51,6 +55,46 @@ async function openFile(path: string) {
   })().catch((err) => console.error("[openFile] phase 2 failed:", err));
 }
 
+// --- Workspaces ---
+
+const WORKSPACE_SUFFIX = ".mdcat-workspace";
+
+async function openWorkspaceFile(path: string) {
+  const workspace = await openWorkspace(path);
+  setWorkspace(workspace);
+  // The open file may now belong to a member root rather than its git root
+  const { filePath } = getState();
+  if (filePath) setContext(await getContext(filePath));
+  if (!filePath) {
+    const title = `mdcat - ${workspace.name}`;
+    document.title = title;
+    getCurrentWindow().setTitle(title);
+  }
+  await loadSidebar();
+}
+
+/** Add a folder as a workspace root, creating the workspace file on first use. */
+async function handleAddWorkspaceRoot() {
+  const dir = await open({ directory: true, multiple: false });
+  if (!dir) return;
+  const state = getState();
+  let workspace = state.workspace;
+  let path = workspace?.path;
+  if (!path) {
+    const picked = await save({
+      defaultPath: `${state.context?.root ?? dir}${WORKSPACE_SUFFIX}`,
+      filters: [{ name: "mdcat Workspace", extensions: [WORKSPACE_SUFFIX.slice(1)] }],
+    });
+    if (!picked) return;
+    path = picked;
+  }
+  const roots = workspace?.roots ?? (state.context ? [{ path: state.context.root, name: "" }] : []);
+  if (roots.some((r) => r.path === dir)) return;
+  // An empty name falls back to the directory name
+  workspace = await saveWorkspace(path, workspace?.name ?? null, [...roots, { path: dir as string, name: "" }]);
+  await openWorkspaceFile(workspace.path);
+}
+
 let benchFirstPaintScheduled = false;
 function scheduleBenchFirstPaint() {
   if (benchFirstPaintScheduled) return;
@@ -73,7 +117,10 @@ async function handleSave() {
 async function handleOpen() {
   const selected = await open({
     multiple: false,
-    filters: [{ name: "Markdown", extensions: ["md", "markdown", "mdown", "mkd"] }],
+    filters: [
+      { name: "Markdown", extensions: ["md", "markdown", "mdown", "mkd"] },
+      { name: "mdcat Workspace", extensions: [WORKSPACE_SUFFIX.slice(1)] },
+    ],
   });
   if (selected) {
     await openFile(selected as string);
@@ -165,7 +212,10 @@ document.addEventListener("keydown", (e) => {
     handleSave();
   }
 
-  if (meta && e.key === "o") {
+  if (meta && e.shiftKey && e.key.toLowerCase() === "o") {
+    e.preventDefault();
+    handleAddWorkspaceRoot();
+  } else if (meta && e.key === "o") {
     e.preventDefault();
     handleOpen();
   }
@@ -209,8 +259,15 @@ listen<string>("open-file", (event) => {
   openFile(event.payload);
 });
 
-// Check for initial file (CLI arg or queued before listener ready)
-getOpenedFile().then((pending) => {
+// Check for initial file (CLI arg or queued before listener ready); a reloaded
+// window picks its workspace back up first
+getWorkspace().then(async (workspace) => {
+  if (workspace) {
+    setWorkspace(workspace);
+    await loadSidebar();
+  }
+  return getOpenedFile();
+}).then((pending) => {
   if (pending) {
     console.log("[mdcat] initial file:", pending);
     openFile(pending);
@@ -1,4 +1,4 @@
-import { AppState, ViewMode, FileContext, FileNode, SearchState } from "./types";
+import { AppState, ViewMode, FileContext, FileNode, SearchState, Workspace } from "./types";
 
 type Listener = (state: AppState) => void;
 
@@ -9,6 +9,7 @@ const state: AppState = {
   content: "",
   mode: "preview",
   context: null,
+  workspace: null,
   tree: [],
   dirty: false,
   search: {
@@ -66,6 +67,11 @@ export function setContext(ctx: FileContext) {
   notify();
 }
 
+export function setWorkspace(workspace: Workspace | null) {
+  state.workspace = workspace;
+  notify();
+}
+
 export function setTree(tree: FileNode[]) {
   state.tree = tree;
   notify();
@@ -1,13 +1,361 @@
+export interface FileMeta {
+  mtime_ms: number;
+  size: number;
+  title?: string;
+  word_count: number;
+  tasks_open: number;
+  tasks_done: number;
+  frontmatter?: Record<string, string>;
+}
+
+export type GitStatus = "untracked" | "staged" | "modified" | "conflicted";
+
 export interface FileNode {
   name: string;
   path: string;
   is_dir: boolean;
+  is_hidden: boolean;
   children?: FileNode[];
+  /** Dir whose children weren't loaded; fetch with getTreeChildren on expand. */
+  truncated?: boolean;
+  meta?: FileMeta;
+  nested_repo?: RepoKind;
+  git?: GitStatus;
+  /** Display title (book order view); `name` stays the file name. */
+  label?: string;
+  /** Synthetic book-order section with no directory behind it. */
+  section?: boolean;
+}
+
+export interface TreeDiff {
+  root: string;
+  added: string[];
+  removed: string[];
+  renamed: { from: string; to: string }[];
+  modified: string[];
+}
+
+export interface ScanProgress {
+  root: string;
+  files_found: number;
+  partial: string[];
+  done: boolean;
+  cancelled: boolean;
+}
+
+export interface WorkspaceSearchOptions {
+  /** Treat the query as a regular expression instead of literal text. */
+  regex: boolean;
+  case_sensitive: boolean;
+}
+
+export interface WorkspaceMatch {
+  path: string;
+  /** 1-based line number. */
+  line: number;
+  /** 1-based column of the match start, in chars. */
+  column: number;
+  length: number;
+  /** The matching line, cut around the match when long. */
+  snippet: string;
+  /** Offset of the match within `snippet`. */
+  snippet_offset: number;
+}
+
+/** Payload of `search-results`; `matches` holds only what's new since the last event. */
+export interface WorkspaceSearchResults {
+  id: number;
+  matches: WorkspaceMatch[];
+  files_searched: number;
+  files_total: number;
+  done: boolean;
+  cancelled: boolean;
+  truncated: boolean;
+}
+
+export interface IndexHit {
+  path: string;
+  /** Frontmatter `title`, else the first H1. */
+  title?: string;
+  score: number;
+  /** 1-based line of the snippet; 0 when only the title matched. */
+  line: number;
+  snippet: string;
+  /** `[start, end)` ranges of matched words within `snippet`. */
+  highlights: [number, number][];
+}
+
+export interface QuickOpenMatch {
+  path: string;
+  root: string;
+  /** Path relative to `root`; `path_positions` index into it. */
+  rel_path: string;
+  title?: string;
+  score: number;
+  /** Indices of matched chars in `rel_path`, for highlighting. */
+  path_positions: number[];
+  /** Indices of matched chars in `title` (when the title matched better). */
+  title_positions: number[];
+}
+
+export interface Heading {
+  level: number;
+  /** Text with inline markup stripped. */
+  text: string;
+  /** GitHub-style anchor, `-1`, `-2`… suffixed when repeated in a file. */
+  slug: string;
+  /** 1-based source line. */
+  line: number;
+}
+
+export interface HeadingMatch extends Heading {
+  path: string;
+  rel_path: string;
+  score: number;
+  /** Indices of matched chars in `text`. */
+  positions: number[];
+}
+
+export type LinkKind = "inline" | "image" | "reference" | "wiki" | "embed";
+
+export interface Link {
+  kind: LinkKind;
+  /** Link text, image alt text or reference label. */
+  text: string;
+  /** Destination as written. */
+  target: string;
+  /** Local file it resolves to (the source itself for a bare `#fragment`); absent for URLs and unresolved wiki links. */
+  path?: string;
+  /** Heading text rather than a slug for wiki links. */
+  fragment?: string;
+  /** Every file an ambiguous wiki link's page name matches. */
+  candidates?: string[];
+  /** 1-based line and column of the link start. */
+  line: number;
+  column: number;
+}
+
+export interface UnusedAsset {
+  path: string;
+  rel_path: string;
+  size: number;
+}
+
+export interface MissingAsset {
+  /** File containing the reference. */
+  path: string;
+  rel_path: string;
+  line: number;
+  column: number;
+  /** Destination as written. */
+  target: string;
+}
+
+export interface AssetReport {
+  root: string;
+  assets_checked: number;
+  references_checked: number;
+  unused: UnusedAsset[];
+  missing: MissingAsset[];
+}
+
+export interface TrashOutcome {
+  /** Folder the assets were moved into, if any were. */
+  trash_dir?: string;
+  moved: string[];
+  /** Referenced again (or gone) since the report; left in place. */
+  refused: string[];
+  errors: string[];
+}
+
+export interface TrashEntry {
+  /** What `restoreTrashed` takes. */
+  id: string;
+  root: string;
+  /** Seconds since the epoch. */
+  trashed_at: number;
+  /** Paths relative to `root` still in the trash. */
+  files: string[];
+}
+
+export interface RestoreOutcome {
+  /** Paths put back, relative to the root. */
+  restored: string[];
+  /** Something exists at the original path again; left in the trash. */
+  refused: string[];
+  errors: string[];
+}
+
+export type CompletionKind = "file" | "asset" | "heading";
+
+export interface LinkCompletion {
+  /** Destination to insert: a relative path, `path#slug` or `#slug`. */
+  label: string;
+  kind: CompletionKind;
+  path: string;
+  /** File title or heading text. */
+  detail?: string;
+  score: number;
+}
+
+export interface WikiResolution {
+  path?: string;
+  /** Heading text after `#`, and its anchor in `path`. */
+  fragment?: string;
+  slug?: string;
+  /** Every file the page name matches, when more than one does. */
+  candidates: string[];
+}
+
+export interface Backlink extends Link {
+  /** File containing the link. */
+  source: string;
+  rel_path: string;
+}
+
+export type BrokenReason = "missing_file" | "missing_anchor" | "ambiguous";
+
+export interface BrokenLink {
+  /** File containing the link. */
+  path: string;
+  rel_path: string;
+  line: number;
+  column: number;
+  /** Destination as written. */
+  target: string;
+  reason: BrokenReason;
+}
+
+export interface LinkReport {
+  root: string;
+  files_checked: number;
+  links_checked: number;
+  broken: BrokenLink[];
+}
+
+export interface LinkEdit {
+  line: number;
+  column: number;
+  /** Destination as written, and as it will be. */
+  from: string;
+  to: string;
+}
+
+export interface FileEdits {
+  path: string;
+  rel_path: string;
+  /** Content hash at preview time; pass the file back to `renameWithLinks` to apply. */
+  stamp: string;
+  edits: LinkEdit[];
+}
+
+export interface RenamePreview {
+  from: string;
+  to: string;
+  /** Files whose links change, at their paths before the move. */
+  files: FileEdits[];
+  total_edits: number;
+}
+
+export interface RenameOutcome {
+  /** Files whose links were rewritten, at their paths after the move. */
+  written: string[];
+  /** Files changed on disk since the preview; their links were left alone. */
+  refused: string[];
+}
+
+export interface ReplaceEdit {
+  /** Index among the file's matches; what `ReplaceSelection.edits` holds. */
+  index: number;
+  line: number;
+  column: number;
+  matched: string;
+  /** `matched` with capture groups expanded. */
+  replacement: string;
+  /** The line(s) around the match, before and after replacing. */
+  before: string;
+  after: string;
+}
+
+export interface FileReplace {
+  path: string;
+  /** Content hash at preview time; send it back with the selection. */
+  stamp: string;
+  edits: ReplaceEdit[];
+}
+
+export interface ReplacePreview {
+  files: FileReplace[];
+  total_edits: number;
+  truncated: boolean;
+}
+
+export interface ReplaceSelection {
+  path: string;
+  stamp: string;
+  edits: number[];
+}
+
+export interface ReplaceOutcome {
+  written: string[];
+  replaced: number;
+  /** Files changed on disk since the preview; left untouched. */
+  refused: string[];
+}
+
+export type SortOrder = "dirs-first" | "files-first" | "mixed";
+
+export type SortKey = "natural" | "name" | "modified" | "title" | "book";
+
+export interface TreeConfig {
+  extensions: string[];
+  include: string[];
+  exclude: string[];
+  skip_dirs: string[];
+  show_hidden_dirs: string[];
+  sort: SortOrder;
+  sort_by: SortKey;
+  max_nodes: number;
+}
+
+export interface LoadedConfig {
+  config: TreeConfig;
+  sources: string[];
+  errors: string[];
+}
+
+export type RepoKind = "main" | "worktree" | "submodule";
+
+export interface GitRepo {
+  work_tree: string;
+  git_dir: string;
+  common_dir: string;
+  kind: RepoKind;
 }
 
 export interface FileContext {
   root: string;
   is_git: boolean;
+  repo: GitRepo | null;
+  repos: GitRepo[];
+  config: LoadedConfig;
+  /** Set for mdBook / MkDocs roots, which offer the "book" sort. */
+  book: BookKind | null;
+}
+
+export type BookKind = "mdbook" | "mkdocs";
+
+export interface WorkspaceRoot {
+  path: string;
+  /** Section label; defaults to the directory name. */
+  name: string;
+}
+
+export interface Workspace {
+  /** The `.mdcat-workspace` file. */
+  path: string;
+  name: string;
+  roots: WorkspaceRoot[];
 }
 
 export type ViewMode = "preview" | "raw";
@@ -25,6 +373,8 @@ export interface AppState {
   content: string;
   mode: ViewMode;
   context: FileContext | null;
+  workspace: Workspace | null;
+  /** With a workspace open, one top-level dir node per root. */
   tree: FileNode[];
   dirty: boolean;
   search: SearchState;
@@ -48,6 +48,23 @@ export function folderOpen(size = 15) {
   );
 }
 
+export function gitBranch(size = 15) {
+  return svg(
+    '<line x1="6" x2="6" y1="3" y2="15"/>' +
+    '<circle cx="18" cy="6" r="3"/><circle cx="6" cy="18" r="3"/>' +
+    '<path d="M18 9a9 9 0 0 1-9 9"/>',
+    size,
+  );
+}
+
+export function arrowUpDown(size = 15) {
+  return svg(
+    '<path d="m21 16-4 4-4-4"/><path d="M17 20V4"/>' +
+    '<path d="m3 8 4-4 4 4"/><path d="M7 4v16"/>',
+    size,
+  );
+}
+
 export function copy(size = 15) {
   return svg(
     '<rect width="14" height="14" x="8" y="8" rx="2" ry="2"/>' +
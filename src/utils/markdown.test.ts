@@ -69,6 +69,18 @@ describe("renderMarkdown", () => {
     expect(html).toBe("");
   });
 
+  it("renders wiki links with their target and label", () => {
+    const html = renderMarkdown("See [[Setup Guide#Install|the guide]] and ![[diagram.png]]");
+    expect(html).toContain('<a href="#" class="wikilink" data-wikilink="Setup Guide#Install">the guide</a>');
+    expect(html).toContain('<a href="#" class="wikilink wikilink-embed" data-wikilink="diagram.png">diagram.png</a>');
+  });
+
+  it("treats [[text]](dest) as a regular link", () => {
+    const html = renderMarkdown("See [[v2]](changelog.md)");
+    expect(html).not.toContain("wikilink");
+    expect(html).toContain('<a href="changelog.md">[v2]</a>');
+  });
+
   it("handles frontmatter-only input", () => {
     const html = renderMarkdown("---\nkey: val\n---\n");
     expect(html).toContain("frontmatter");
@@ -1,4 +1,4 @@
-import { Marked, Token, Tokens, TokensList } from "marked";
+import { Marked, Token, Tokens, TokensList, TokenizerAndRendererExtension } from "marked";
 import hljs from "highlight.js";
 import { convertFileSrc } from "@tauri-apps/api/core";
 import { renderMermaidSVG } from "beautiful-mermaid";
@@ -110,7 +110,30 @@ const renderer = {
   },
 };
 
-const marked = new Marked({ renderer });
+/**
+ * Obsidian-style `[[page#heading|label]]` and `![[embed]]`. Rendered as links
+ * carrying the target in `data-wikilink`; the preview resolves them on click.
+ */
+const wikilink: TokenizerAndRendererExtension = {
+  name: "wikilink",
+  level: "inline",
+  start(src: string) {
+    return src.match(/!?\[\[/)?.index;
+  },
+  tokenizer(src: string) {
+    // `[[x]](dest)` is a regular link whose text is `[x]`
+    const m = /^(!?)\[\[([^[\]\n|]+)(?:\|([^[\]\n]+))?\]\](?!\()/.exec(src);
+    if (!m) return undefined;
+    return { type: "wikilink", raw: m[0], target: m[2].trim(), label: (m[3] ?? m[2]).trim(), embed: m[1] === "!" };
+  },
+  renderer(token: Tokens.Generic): string {
+    const cls = token.embed ? "wikilink wikilink-embed" : "wikilink";
+    const target = escapeHtml(token.target).replace(/"/g, "&quot;");
+    return `<a href="#" class="${cls}" data-wikilink="${target}">${escapeHtml(token.label)}</a>`;
+  },
+};
+
+const marked = new Marked({ renderer, extensions: [wikilink] });
 
 marked.setOptions({
   gfm: true,
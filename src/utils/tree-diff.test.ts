@@ -0,0 +1,87 @@
+import { describe, it, expect } from "vitest";
+import { applyTreeDiff } from "./tree-diff";
+import { FileNode, TreeDiff } from "../types";
+
+function file(path: string): FileNode {
+  return { name: path.split("/").pop()!, path, is_dir: false, is_hidden: false };
+}
+
+function dir(path: string, children?: FileNode[]): FileNode {
+  const node: FileNode = { name: path.split("/").pop()!, path, is_dir: true, is_hidden: false };
+  if (children) node.children = children;
+  else node.truncated = true;
+  return node;
+}
+
+function diff(changes: Partial<TreeDiff>): TreeDiff {
+  return { root: "/r", added: [], removed: [], renamed: [], modified: [], ...changes };
+}
+
+/** Serves levels from a fixed listing and records which dirs were fetched. */
+function loader(levels: Record<string, FileNode[]>) {
+  const fetched: string[] = [];
+  const load = async (_root: string, dir: string) => {
+    fetched.push(dir);
+    return levels[dir] ?? [];
+  };
+  return { load, fetched };
+}
+
+describe("applyTreeDiff", () => {
+  it("drops removed files and the dirs they leave empty", async () => {
+    const nodes = [dir("/r/a", [dir("/r/a/b", [file("/r/a/b/x.md")])]), file("/r/y.md")];
+    const { load, fetched } = loader({});
+    await applyTreeDiff(nodes, diff({ removed: ["/r/a/b/x.md"] }), load);
+    expect(nodes.map((n) => n.path)).toEqual(["/r/y.md"]);
+    expect(fetched).toEqual([]);
+  });
+
+  it("refetches the parent level of added files and keeps loaded subtrees", async () => {
+    const sub = [file("/r/a/x.md")];
+    const nodes = [dir("/r/a", sub), file("/r/z.md")];
+    const { load, fetched } = loader({
+      "/r": [dir("/r/a"), file("/r/b.md"), file("/r/z.md")],
+    });
+    await applyTreeDiff(nodes, diff({ added: ["/r/b.md"] }), load);
+    expect(fetched).toEqual(["/r"]);
+    expect(nodes.map((n) => n.path)).toEqual(["/r/a", "/r/b.md", "/r/z.md"]);
+    expect(nodes[0].children).toBe(sub);
+    expect(nodes[0].truncated).toBe(false);
+  });
+
+  it("lists a file in a new dir from the nearest loaded ancestor", async () => {
+    const nodes = [dir("/r/a", [file("/r/a/x.md")])];
+    const { load, fetched } = loader({
+      "/r/a": [dir("/r/a/new"), file("/r/a/x.md")],
+    });
+    await applyTreeDiff(nodes, diff({ added: ["/r/a/new/deep/n.md"] }), load);
+    expect(fetched).toEqual(["/r/a"]);
+    expect(nodes[0].children!.map((n) => n.path)).toEqual(["/r/a/new", "/r/a/x.md"]);
+  });
+
+  it("moves renamed files and leaves unloaded dirs to the lazy load", async () => {
+    const nodes = [dir("/r/a", [file("/r/a/x.md"), file("/r/a/w.md")]), dir("/r/c")];
+    const { load, fetched } = loader({
+      "/r/a": [file("/r/a/w.md"), file("/r/a/y.md")],
+    });
+    await applyTreeDiff(
+      nodes,
+      diff({
+        renamed: [
+          { from: "/r/a/x.md", to: "/r/a/y.md" },
+          { from: "/r/a/w.md", to: "/r/c/w.md" },
+        ],
+      }),
+      load,
+    );
+    expect(fetched).toEqual(["/r/a"]);
+    expect(nodes[0].children!.map((n) => n.path)).toEqual(["/r/a/w.md", "/r/a/y.md"]);
+  });
+
+  it("ignores modified paths that aren't in the tree", async () => {
+    const nodes = [file("/r/a.md")];
+    const { load, fetched } = loader({});
+    await applyTreeDiff(nodes, diff({ modified: ["/r/mkdocs.yml"] }), load);
+    expect(fetched).toEqual([]);
+  });
+});
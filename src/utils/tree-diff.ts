@@ -0,0 +1,81 @@
+import { FileNode, TreeDiff } from "../types";
+
+/** Fetches the first level of `dir` under `root`, sorted and decorated. */
+export type LevelLoader = (root: string, dir: string) => Promise<FileNode[]>;
+
+/**
+ * The list holding the children of `dir` in `nodes`, where `nodes` are the
+ * loaded children of `root`; undefined if `dir` isn't loaded or truncated.
+ */
+function childList(nodes: FileNode[], root: string, dir: string): FileNode[] | undefined {
+  if (dir === root) return nodes;
+  for (const node of nodes) {
+    if (!node.is_dir) continue;
+    if (node.path === dir) return node.children;
+    if (dir.startsWith(`${node.path}/`) && node.children) return childList(node.children, node.path, dir);
+  }
+  return undefined;
+}
+
+function findEntry(nodes: FileNode[], path: string): FileNode | undefined {
+  for (const node of nodes) {
+    if (node.path === path) return node;
+    if (node.is_dir && node.children && path.startsWith(`${node.path}/`)) {
+      return findEntry(node.children, path);
+    }
+  }
+  return undefined;
+}
+
+function parentDir(path: string): string {
+  return path.slice(0, path.lastIndexOf("/"));
+}
+
+/** Drop `path`, then any dir it leaves empty, short of `root` and `keep`. */
+function removeEntry(nodes: FileNode[], root: string, path: string, keep: Set<string>) {
+  const dir = parentDir(path);
+  const list = childList(nodes, root, dir);
+  const index = list?.findIndex((n) => n.path === path) ?? -1;
+  if (!list || index < 0) return;
+  list.splice(index, 1);
+  if (list.length === 0 && dir !== root && !keep.has(dir)) removeEntry(nodes, root, dir, keep);
+}
+
+/**
+ * Apply a "tree-changed" diff to `nodes`, the loaded children of `diff.root`,
+ * in place. Removed entries are dropped; each dir that gained, renamed or
+ * changed files has its first level refetched with `loadLevel` (so order,
+ * titles and git status stay the backend's), keeping subtrees already loaded
+ * below it. Entries under dirs that aren't loaded are left for the lazy load.
+ */
+export async function applyTreeDiff(nodes: FileNode[], diff: TreeDiff, loadLevel: LevelLoader) {
+  const root = diff.root;
+  const changed = [
+    ...diff.added,
+    ...diff.renamed.map((r) => r.to),
+    // `modified` also carries non-markdown files such as `mkdocs.yml`
+    ...diff.modified.filter((path) => findEntry(nodes, path)),
+  ];
+  const dirs = new Set<string>();
+  for (const path of changed) {
+    // the innermost dir already shown lists the new entry (or the new dir holding it)
+    let dir = parentDir(path);
+    while (dir !== root && dir.startsWith(`${root}/`) && !findEntry(nodes, dir)) dir = parentDir(dir);
+    if (childList(nodes, root, dir)) dirs.add(dir);
+  }
+
+  for (const path of [...diff.removed, ...diff.renamed.map((r) => r.from)]) {
+    removeEntry(nodes, root, path, dirs);
+  }
+
+  for (const dir of dirs) {
+    const list = childList(nodes, root, dir);
+    if (!list) continue; // its own dir went away
+    const loaded = new Map(list.map((n) => [n.path, n]));
+    const fresh = (await loadLevel(root, dir)).map((node) => {
+      const prev = loaded.get(node.path);
+      return node.is_dir && prev?.children ? { ...node, children: prev.children, truncated: false } : node;
+    });
+    list.splice(0, list.length, ...fresh);
+  }
+}
@@ -1,10 +1,17 @@
 import { listen, UnlistenFn } from "@tauri-apps/api/event";
-import { watchFile, unwatchFile } from "../ipc";
+import { watchFile, unwatchFile, watchTree, unwatchTree } from "../ipc";
+import { TreeDiff } from "../types";
 
 type WatchCallback = () => void;
+/** `diff` is null when only git status changed or the background index finished. */
+type TreeCallback = (diff: TreeDiff | null) => void;
 
 let currentPath: string | null = null;
 let unlisten: UnlistenFn | null = null;
+let currentRoots: string[] = [];
+let unlistenTree: UnlistenFn | null = null;
+let unlistenGit: UnlistenFn | null = null;
+let unlistenIndexed: UnlistenFn | null = null;
 
 export async function startWatching(path: string, onChange: WatchCallback) {
   await stopWatching();
@@ -33,3 +40,49 @@ export async function stopWatching() {
 export function getWatchedPath(): string | null {
   return currentPath;
 }
+
+/**
+ * Watch whole roots (one per workspace section); the backend keeps each tree
+ * in memory and sends diffs. Returns false if `roots` were already being watched.
+ */
+export async function startTreeWatching(roots: string[], onChange: TreeCallback): Promise<boolean> {
+  if (sameRoots(currentRoots, roots) && unlistenTree) return false;
+  await stopTreeWatching();
+  currentRoots = roots;
+
+  unlistenTree = await listen<TreeDiff>("tree-changed", (event) => {
+    if (currentRoots.includes(event.payload.root)) onChange(event.payload);
+  });
+  unlistenGit = await listen<string>("git-status-changed", (event) => {
+    if (currentRoots.includes(event.payload)) onChange(null);
+  });
+  unlistenIndexed = await listen<string>("tree-indexed", (event) => {
+    if (currentRoots.includes(event.payload)) onChange(null);
+  });
+
+  await watchTree(roots);
+  return true;
+}
+
+function sameRoots(a: string[], b: string[]): boolean {
+  return a.length === b.length && a.every((root, i) => root === b[i]);
+}
+
+export async function stopTreeWatching() {
+  if (unlistenTree) {
+    unlistenTree();
+    unlistenTree = null;
+  }
+  if (unlistenGit) {
+    unlistenGit();
+    unlistenGit = null;
+  }
+  if (unlistenIndexed) {
+    unlistenIndexed();
+    unlistenIndexed = null;
+  }
+  if (currentRoots.length > 0) {
+    await unwatchTree();
+  }
+  currentRoots = [];
+}